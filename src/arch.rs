@@ -0,0 +1,46 @@
+use super::protos::crash_report::CrashReport;
+use super::text::code_type;
+
+/// CPU architecture of the crashed process.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Arch {
+    Arm,
+    Arm64,
+    /// 64-bit ARM with 32-bit pointers, as on watchOS.
+    Arm64_32,
+    X86,
+    X86_64,
+    PowerPC,
+    Unknown,
+}
+
+impl Arch {
+    /// The architecture of `report`, derived the same way as the Code Type line.
+    pub fn of(report: &CrashReport) -> Arch {
+        match code_type(report).0 {
+            "ARM" => Arch::Arm,
+            "ARM-64" => Arch::Arm64,
+            "ARM64_32" => Arch::Arm64_32,
+            "X86" => Arch::X86,
+            "X86-64" => Arch::X86_64,
+            "PPC" => Arch::PowerPC,
+            _ => Arch::Unknown,
+        }
+    }
+
+    /// Whether pointers are 64 bits wide.
+    pub fn is_lp64(self) -> bool {
+        match self {
+            Arch::Arm | Arch::Arm64_32 | Arch::X86 | Arch::PowerPC => false,
+            Arch::Arm64 | Arch::X86_64 | Arch::Unknown => true,
+        }
+    }
+
+    pub fn is_arm(self) -> bool {
+        self == Arch::Arm || self == Arch::Arm64 || self == Arch::Arm64_32
+    }
+
+    pub fn is_x86(self) -> bool {
+        self == Arch::X86 || self == Arch::X86_64
+    }
+}
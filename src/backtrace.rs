@@ -0,0 +1,346 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::Path;
+
+use failure::ResultExt;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::arch::Arch;
+use super::blame::BlameLine;
+use super::image_kind::{ImageClassifier, ImageKind};
+use super::options::Options;
+use super::protos::crash_report::*;
+use super::registers::{self, RegisterRole};
+use super::source::SourceContext;
+use super::symbolicate::{Location, Symbolicate};
+use crate::error::{Error, ErrorKind};
+
+/// Where the name shown for a frame came from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolSource {
+    /// `StackFrame.symbol`, the best guess made on the device.
+    Client,
+    /// The function containing the pc in the dSYM.
+    Dwarf,
+    /// The nearest preceding symbol in the dSYM symbol table.
+    SymbolTable,
+}
+
+/// How much we trust the name shown for a frame.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    /// From debug info, or a client symbol that the dSYM agrees with.
+    High,
+    /// From the symbol table, which only knows where functions start.
+    Medium,
+    /// A client symbol we could not check, a disagreement, or no name at all.
+    Low,
+}
+
+/// A client side symbol the dSYM doesn't agree with. Addresses are absolute.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Disagreement {
+    /// The client symbol doesn't start where the DWARF function containing the pc does.
+    ClientStart { client_start: u64, function_start: u64, function_end: u64 },
+    /// The client symbol doesn't start where the nearest symbol table entry does.
+    SymbolTableStart { client_start: u64, symbol_start: u64 },
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Disagreement::ClientStart { client_start, function_start, function_end } => {
+                write!(f, "client symbol starts at {:#x}, but the DWARF function spans {:#x}-{:#x}", client_start, function_start, function_end)
+            },
+            Disagreement::SymbolTableStart { client_start, symbol_start } => {
+                write!(f, "client symbol starts at {:#x}, but the symbol table has a symbol at {:#x}", client_start, symbol_start)
+            },
+        }
+    }
+}
+
+/// Where the name of a frame came from and how far it can be trusted.
+#[derive(Clone, Debug, Serialize)]
+pub struct Provenance {
+    /// Source of the name shown for the frame, if it has one.
+    pub source: Option<SymbolSource>,
+    pub confidence: Confidence,
+    /// Sources that disagree with each other.
+    pub disagreements: Vec<Disagreement>,
+}
+
+/// A stack frame from the report together with everything we could work out about it.
+pub struct BacktraceFrame {
+    /// Position of the frame in its backtrace.
+    pub index: usize,
+    /// Instruction pointer.
+    pub pc: u64,
+    /// Full path of the image containing the pc, if it is known.
+    pub image_path: Option<String>,
+    /// Where the image containing the pc comes from.
+    pub image_kind: Option<ImageKind>,
+    /// Base address of the image containing the pc, or 0 if not known.
+    pub image_base: u64,
+    /// Offset of the pc from `image_base`.
+    pub pc_offset: u64,
+    /// Client side symbol name with the platform symbol prefix removed.
+    pub symbol: Option<String>,
+    /// Offset of the pc from the start of the client side symbol.
+    pub symbol_offset: Option<u64>,
+    /// Demangled function name from the dSYM.
+    pub function: Option<String>,
+    /// Offset of the pc from the start of the DWARF function.
+    pub function_offset: Option<u64>,
+    /// Source location from the dSYM.
+    pub location: Option<Location>,
+    /// Source lines around `location`, when a source root is configured.
+    pub source: Option<SourceContext>,
+    /// The commit that last touched `location` before the build.
+    pub blame: Option<BlameLine>,
+    /// Link to `location` on source hosting.
+    pub link: Option<String>,
+    /// Where the name comes from. Always set by `symbolicate_frame`.
+    pub provenance: Provenance,
+    /// The frame is not in the report, it was recovered from the link register.
+    pub synthetic: bool,
+}
+
+impl BacktraceFrame {
+    /// File name of the image containing the pc, or `???`.
+    pub fn image_name(&self) -> String {
+        self.image_path.as_ref()
+            .map(|path| {
+                Path::new(path).file_name()
+                    .unwrap_or(OsStr::new("???"))
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .unwrap_or_else(|| String::from("???"))
+    }
+
+    /// Whether the DWARF function name should be shown instead of the symbol.
+    pub fn shows_function(&self, options: &Options) -> bool {
+        self.function.is_some() && self.function_offset.is_some() && (self.symbol.is_none() || options.prefer_dwarf_names)
+    }
+}
+
+/// Remove the platform symbol prefix.
+pub(crate) fn strip_symbol_prefix<'a>(symbol_name: &'a str, report: &CrashReport) -> &'a str {
+    /* Apple strips the _ symbol prefix in their reports. Only OS X makes use of an
+     * underscore symbol prefix by default. */
+    if symbol_name.starts_with("_") && symbol_name.len() > 1 {
+        match report.get_system_info().get_operating_system() {
+            CrashReport_SystemInfo_OperatingSystem::MAC_OS_X |
+            CrashReport_SystemInfo_OperatingSystem::IPHONE_OS |
+            CrashReport_SystemInfo_OperatingSystem::IPHONE_SIMULATOR => {
+                let (_, s) = symbol_name.split_at(1);
+                return s;
+            },
+            _ => {
+                /* Symbol prefix rules are unknown for this OS! */
+            }
+        }
+    }
+    symbol_name
+}
+
+/// Symbolicate a single stack frame against the images in `report` and the dSYMs in `symbolicate`.
+pub fn symbolicate_frame(frame: &CrashReport_Thread_StackFrame, idx: usize, report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<BacktraceFrame, Error> {
+    let mut ret = BacktraceFrame {
+        index: idx,
+        pc: frame.get_pc(),
+        image_path: None,
+        image_kind: None,
+        image_base: 0,
+        pc_offset: 0,
+        symbol: None,
+        symbol_offset: None,
+        function: None,
+        function_offset: None,
+        location: None,
+        source: None,
+        blame: None,
+        link: None,
+        provenance: Provenance {
+            source: None,
+            confidence: Confidence::Low,
+            disagreements: Vec::new(),
+        },
+        synthetic: false,
+    };
+
+    let client_start = if frame.has_symbol() {
+        Some(frame.get_symbol().get_start_address())
+    } else {
+        None
+    };
+    let mut corroborated = false;
+    let mut table_symbol = None;
+
+    if let Some(image) = image_for_address(report, frame.get_pc()) {
+        ret.image_path = Some(image.get_name().into());
+        ret.image_kind = Some(match options.image_classifier {
+            Some(classifier) => classifier.classify(report, image.get_name()),
+            None => ImageClassifier::default().classify(report, image.get_name()),
+        });
+        ret.image_base = image.get_base_address();
+        ret.pc_offset = frame.get_pc() - ret.image_base;
+
+        /* Fetch the dSYM if it exists */
+        let lookup = image_uuid(image).and_then(|uuid| symbolicate.and_then(|s| s.get(&uuid)));
+        if let Some(lookup) = lookup {
+            /* Frames come innermost first. The location is the line of the pc, inside any inlined calls, but
+             * the name is the outermost function, since the function range and offset are of that one. */
+            for (depth, dsym_frame) in lookup.find_frames(ret.pc_offset)?.enumerate() {
+                let dsym_frame = dsym_frame.context(ErrorKind::Probe(lookup.name.clone(), ret.pc_offset))?;
+                ret.function = dsym_frame.function_name();
+                if depth == 0 {
+                    ret.location = dsym_frame.location();
+                }
+            }
+
+            /* Check the client symbol against the dSYM */
+            if let Some((start, end)) = lookup.function_range(ret.pc_offset) {
+                ret.function_offset = Some(ret.pc_offset - start);
+                let (function_start, function_end) = (ret.image_base + start, ret.image_base + end);
+                match client_start {
+                    Some(client_start) if client_start == function_start => corroborated = true,
+                    Some(client_start) => ret.provenance.disagreements.push(Disagreement::ClientStart { client_start, function_start, function_end }),
+                    None => {},
+                }
+            } else if let Some((name, start)) = lookup.find_symbol(ret.pc_offset) {
+                let symbol_start = ret.image_base + start;
+                match client_start {
+                    Some(client_start) if client_start == symbol_start => corroborated = true,
+                    Some(client_start) => ret.provenance.disagreements.push(Disagreement::SymbolTableStart { client_start, symbol_start }),
+                    None => table_symbol = Some((strip_symbol_prefix(name, report).to_string(), ret.pc_offset - start)),
+                }
+            }
+        }
+    }
+
+    if let (Some(source), Some(location)) = (options.source, ret.location.as_ref()) {
+        ret.source = source.context(location, options.context_lines);
+    }
+
+    /* Only app code is in the repository, so don't run git for system frames */
+    if let (Some(blame), Some(location)) = (options.blame, ret.location.as_ref()) {
+        if ret.image_path.iter().any(|path| is_in_app(report, path)) {
+            ret.blame = blame.blame(report, location);
+        }
+    }
+
+    if let (Some(links), Some(location)) = (options.links, ret.location.as_ref()) {
+        ret.link = links.link(report, location, options);
+    }
+
+    if frame.has_symbol() {
+        ret.symbol = Some(strip_symbol_prefix(frame.get_symbol().get_name(), report).into());
+        ret.symbol_offset = Some(frame.get_pc() - frame.get_symbol().get_start_address());
+    } else if ret.function.is_none() {
+        /* The symbol table is only a fallback, it can't tell where a function ends */
+        if let Some((name, offset)) = table_symbol {
+            ret.symbol = Some(name);
+            ret.symbol_offset = Some(offset);
+            ret.provenance.source = Some(SymbolSource::SymbolTable);
+        }
+    }
+
+    if ret.shows_function(options) {
+        ret.provenance.source = Some(SymbolSource::Dwarf);
+    } else if frame.has_symbol() {
+        ret.provenance.source = Some(SymbolSource::Client);
+    }
+    ret.provenance.confidence = match ret.provenance.source {
+        _ if !ret.provenance.disagreements.is_empty() => Confidence::Low,
+        Some(SymbolSource::Dwarf) => Confidence::High,
+        Some(SymbolSource::Client) if corroborated => Confidence::High,
+        Some(SymbolSource::SymbolTable) => Confidence::Medium,
+        _ => Confidence::Low,
+    };
+
+    Ok(ret)
+}
+
+/// Symbolicate every frame in `frames`.
+pub fn symbolicate_frames(frames: &[CrashReport_Thread_StackFrame], report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<Vec<BacktraceFrame>, Error> {
+    let mut ret = Vec::with_capacity(frames.len());
+    for (idx, frame) in frames.iter().enumerate() {
+        ret.push(symbolicate_frame(frame, idx, report, symbolicate, options)?);
+    }
+    Ok(ret)
+}
+
+/// Symbolicate the backtrace of `thread`.
+///
+/// With `options.recover_lr_frame` set, this adds the caller that a crash in an ARM leaf function leaves out
+/// of the backtrace of the crashed thread, when the link register points into an image and isn't the pc of
+/// the next frame.
+pub fn symbolicate_thread(thread: &CrashReport_Thread, report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<Vec<BacktraceFrame>, Error> {
+    let mut ret = symbolicate_frames(thread.get_frames(), report, symbolicate, options)?;
+    if !options.recover_lr_frame || !thread.get_crashed() || ret.is_empty() {
+        return Ok(ret);
+    }
+    let lr = match registers::find_by_role(Arch::of(report), thread, RegisterRole::LinkRegister) {
+        Some(lr) => lr,
+        None => return Ok(ret),
+    };
+    if image_for_address(report, lr).is_none() || ret.get(1).map(|frame| frame.pc == lr).unwrap_or(false) {
+        return Ok(ret);
+    }
+
+    let mut frame = CrashReport_Thread_StackFrame::new();
+    frame.set_pc(lr);
+    let mut recovered = symbolicate_frame(&frame, 1, report, symbolicate, options)?;
+    recovered.synthetic = true;
+    for frame in ret.iter_mut().skip(1) {
+        frame.index += 1;
+    }
+    ret.insert(1, recovered);
+    Ok(ret)
+}
+
+/// Symbolicate the backtraces of all threads of `report`, in the order of `CrashReport.threads`.
+pub fn symbolicate_threads(report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<Vec<Vec<BacktraceFrame>>, Error> {
+    report.get_threads().iter()
+        .map(|thread| symbolicate_thread(thread, report, symbolicate, options))
+        .collect()
+}
+
+/// Find the binary image that contains `address`.
+pub fn image_for_address(report: &CrashReport, address: u64) -> Option<&CrashReport_BinaryImage> {
+    for image in report.get_binary_images() {
+        let base_address = image.get_base_address();
+        if base_address <= address && address < (base_address + image.get_size()) {
+            return Some(image);
+        }
+    }
+
+    /* Not found */
+    None
+}
+
+/// Parse the UUID of `image`, if it has a valid one.
+pub fn image_uuid(image: &CrashReport_BinaryImage) -> Option<Uuid> {
+    if image.has_uuid() {
+        Uuid::from_slice(image.get_uuid()).ok()
+    } else {
+        None
+    }
+}
+
+/// Whether the image at `image_path` ships with the app: the main executable, or anything inside its `.app` bundle.
+pub fn is_in_app(report: &CrashReport, image_path: &str) -> bool {
+    let process_path = report.get_process_info().get_process_path();
+    if process_path.is_empty() {
+        return false;
+    }
+    match process_path.find(".app/") {
+        Some(idx) => image_path.starts_with(&process_path[..idx + 5]),
+        None => image_path == process_path,
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use failure::ResultExt;
+use goblin::{peek_bytes, Hint};
+use goblin::mach;
+use uuid::Uuid;
+
+use super::symbolicate::{mach_uuid, regions, slide, Region};
+use crate::error::{Error, ErrorKind};
+
+/// A Mach-O image from the build, with its code and data.
+///
+/// dSYMs only carry debug info, so anything that needs the instructions or constant strings of an image
+/// has to read them from here.
+pub struct Binary {
+    pub path: PathBuf,
+    pub uuid: Option<Uuid>,
+    pub cpu_type: u64,
+    data: Vec<u8>,
+    /// Segments as image offsets, with their file offset and file size.
+    segments: Vec<(Region, u64, u64)>,
+    sections: Vec<Region>,
+}
+
+impl Binary {
+    fn load(path: &Path, data: Vec<u8>) -> Result<Binary, Error> {
+        let kind = ErrorKind::Binary(path.to_path_buf());
+        let (uuid, cpu_type, segments, sections) = {
+            let macho = mach::MachO::parse(&data, 0).context(kind.clone())?;
+            let slide_addr = slide(&macho).unwrap_or(0);
+            let (_, sections) = regions(&macho, slide_addr);
+            let segments = macho.segments.iter()
+                .filter(|segment| segment.vmaddr >= slide_addr && segment.vmsize > 0)
+                .map(|segment| {
+                    let region = Region {
+                        segment: segment.name().unwrap_or("").to_string(),
+                        section: None,
+                        start: segment.vmaddr - slide_addr,
+                        end: segment.vmaddr + segment.vmsize - slide_addr,
+                    };
+                    (region, segment.fileoff, segment.filesize)
+                })
+                .collect();
+            (mach_uuid(&macho), macho.header.cputype as u64, segments, sections)
+        };
+        Ok(Binary {
+            path: path.to_path_buf(),
+            uuid,
+            cpu_type,
+            data,
+            segments,
+            sections,
+        })
+    }
+
+    /// Load every architecture in the Mach-O or fat file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Vec<Binary>, Error> {
+        let path = path.as_ref();
+        let kind = ErrorKind::Binary(path.to_path_buf());
+        let data = std::fs::read(path).context(kind.clone())?;
+        if data.len() < 16 {
+            return Err(kind.into());
+        }
+        let mut bytes = [0u8; 16];
+        bytes.clone_from_slice(&data[..16]);
+        match peek_bytes(&bytes).context(kind.clone())? {
+            Hint::MachFat(_) => {
+                let multi = mach::MultiArch::new(&data).context(kind.clone())?;
+                let mut ret = Vec::new();
+                for arch in multi.iter_arches() {
+                    let arch = arch.context(kind.clone())?;
+                    ret.push(Binary::load(path, arch.slice(&data).to_vec())?);
+                }
+                Ok(ret)
+            },
+            Hint::Mach(_) => Ok(vec![Binary::load(path, data)?]),
+            _ => Err(kind.into()),
+        }
+    }
+
+    /// Segments as image offsets.
+    pub fn segments(&self) -> Vec<&Region> {
+        self.segments.iter().map(|segment| &segment.0).collect()
+    }
+
+    /// The section containing the image offset `offset`.
+    pub fn find_section(&self, offset: u64) -> Option<&Region> {
+        self.sections.iter().find(|region| region.start <= offset && offset < region.end)
+    }
+
+    /// Up to `len` bytes of the image at the image offset `offset`, stopping at the end of the segment.
+    pub fn read(&self, offset: u64, len: u64) -> Option<&[u8]> {
+        let (region, fileoff, filesize) = self.segments.iter()
+            .find(|segment| segment.0.start <= offset && offset < segment.0.end)?;
+        let within = offset - region.start;
+        if within >= *filesize {
+            return None;
+        }
+        let start = (fileoff + within) as usize;
+        let end = (fileoff + within + len.min(filesize - within)) as usize;
+        self.data.get(start..end)
+    }
+
+    /// The NUL terminated string at the image offset `offset`.
+    pub fn read_c_string(&self, offset: u64) -> Option<String> {
+        let bytes = self.read(offset, 4096)?;
+        let end = bytes.iter().position(|b| *b == 0)?;
+        Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+/// Application binaries by UUID.
+#[derive(Default)]
+pub struct Binaries {
+    files: HashMap<Uuid, Binary>,
+}
+
+impl Binaries {
+    pub fn new() -> Binaries {
+        Binaries::default()
+    }
+
+    /// Add every architecture of the Mach-O or fat file at `path`.
+    pub fn add<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        for binary in Binary::open(path)? {
+            if let Some(uuid) = binary.uuid {
+                self.files.insert(uuid, binary);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, uuid: &Uuid) -> Option<&Binary> {
+        self.files.get(uuid)
+    }
+}
@@ -0,0 +1,260 @@
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::rc::Rc;
+
+use failure::ResultExt;
+use regex::Regex;
+use serde::Serialize;
+
+use super::backtrace::BacktraceFrame;
+use super::protos::crash_report::CrashReport;
+use super::source::PathMap;
+use super::symbolicate::Location;
+use crate::error::{Error, ErrorKind};
+
+/// Number of suspect commits the report summaries list.
+pub const SUSPECT_COMMITS : usize = 5;
+
+/// Finds the commit in versions like `17.10.07.22.ec9e94c` or `1.0 (17.10.07.22.ec9e94c)`.
+const DEFAULT_COMMIT_PATTERN : &str = r"(?:^|[.\s(+-])([0-9a-fA-F]{7,40})\)?$";
+
+/// Finds the build commit in `ApplicationInfo.version`.
+#[derive(Clone, Debug)]
+pub struct CommitPattern(Regex);
+
+impl CommitPattern {
+    /// The first capture group of `pattern` is the commit.
+    pub fn new(pattern: &str) -> Result<CommitPattern, Error> {
+        Ok(CommitPattern(Regex::new(pattern).context(ErrorKind::Pattern(pattern.into()))?))
+    }
+
+    pub fn find<'v>(&self, version: &'v str) -> Option<&'v str> {
+        self.0.captures(version)
+            .and_then(|captures| captures.get(1))
+            .map(|commit| commit.as_str())
+    }
+}
+
+impl Default for CommitPattern {
+    fn default() -> CommitPattern {
+        CommitPattern(Regex::new(DEFAULT_COMMIT_PATTERN).unwrap())
+    }
+}
+
+/// The commit that last touched a source line.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct BlameLine {
+    pub commit: String,
+    pub author: String,
+    pub author_mail: String,
+    /// Author time in seconds since the epoch.
+    pub author_time: i64,
+    pub summary: String,
+}
+
+/// Someone who last touched one or more lines in a backtrace.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Owner {
+    pub author: String,
+    pub author_mail: String,
+    pub frames: usize,
+}
+
+/// Likely owners and suspect commits for a backtrace.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BlameSummary {
+    /// Authors ordered by the number of frames they last touched.
+    pub owners: Vec<Owner>,
+    /// Distinct commits, most recent first.
+    pub suspects: Vec<BlameLine>,
+}
+
+/// Blames symbolicated lines in a local git repository, as of the commit an app was built from.
+///
+/// This runs the `git` command line tool.
+pub struct GitBlame {
+    repo: PathBuf,
+    paths: PathMap,
+    commit_pattern: CommitPattern,
+    commits: RefCell<HashMap<String, Option<String>>>,
+    trees: RefCell<HashMap<String, Rc<Vec<String>>>>,
+    lines: RefCell<HashMap<(String, String, u64), Option<BlameLine>>>,
+}
+
+impl GitBlame {
+    pub fn open<P: Into<PathBuf>>(repo: P) -> Result<GitBlame, Error> {
+        let repo = repo.into();
+        git(&repo, &["rev-parse", "--git-dir"]).ok_or_else(|| ErrorKind::Git(repo.clone()))?;
+        Ok(GitBlame {
+            repo,
+            paths: PathMap::new(),
+            commit_pattern: CommitPattern::default(),
+            commits: RefCell::new(HashMap::new()),
+            trees: RefCell::new(HashMap::new()),
+            lines: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub fn repo(&self) -> &Path {
+        &self.repo
+    }
+
+    /// Rewrite DWARF paths to paths relative to the repository root.
+    ///
+    /// Paths without a matching rule are matched against the files in the build commit by their longest suffix.
+    pub fn set_path_map(&mut self, paths: PathMap) {
+        self.paths = paths;
+    }
+
+    pub fn set_commit_pattern(&mut self, pattern: CommitPattern) {
+        self.commit_pattern = pattern;
+        self.commits.borrow_mut().clear();
+    }
+
+    /// The full commit id a report was built from, if the version names one that exists in the repository.
+    pub fn commit_for_version(&self, version: &str) -> Option<String> {
+        if let Some(commit) = self.commits.borrow().get(version) {
+            return commit.clone();
+        }
+        let commit = self.commit_pattern.find(version)
+            .and_then(|short| {
+                let spec = format!("{}^{{commit}}", short);
+                git(&self.repo, &["rev-parse", "--verify", "--quiet", &spec])
+            })
+            .map(|full| full.trim().to_string());
+        self.commits.borrow_mut().insert(version.into(), commit.clone());
+        commit
+    }
+
+    /// The path of a DWARF source file in the tree of `commit`.
+    pub fn resolve(&self, commit: &str, file: &str) -> Option<String> {
+        let tree = self.tree(commit)?;
+        let path = Path::new(file);
+        if let Some(mapped) = self.paths.map(path) {
+            let mapped = mapped.to_string_lossy().into_owned();
+            return if tree.contains(&mapped) {
+                Some(mapped)
+            } else {
+                None
+            };
+        }
+
+        /* Prefer the longest matching suffix, which is the first one found walking the components forwards */
+        let components : Vec<String> = path.iter()
+            .map(|c| c.to_string_lossy().into_owned())
+            .filter(|c| c != "/")
+            .collect();
+        for start in 0..components.len() {
+            let candidate = components[start..].join("/");
+            if tree.contains(&candidate) {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    /// Blame `line` of `path` as of `commit`.
+    pub fn blame_line(&self, commit: &str, path: &str, line: u64) -> Option<BlameLine> {
+        let key = (commit.to_string(), path.to_string(), line);
+        if let Some(blame) = self.lines.borrow().get(&key) {
+            return blame.clone();
+        }
+        let range = format!("{},{}", line, line);
+        let blame = git(&self.repo, &["blame", "--porcelain", "-L", &range, commit, "--", path])
+            .and_then(|output| parse_porcelain(&output));
+        self.lines.borrow_mut().insert(key, blame.clone());
+        blame
+    }
+
+    /// Blame a symbolicated location as of the commit `report` was built from.
+    pub fn blame(&self, report: &CrashReport, location: &Location) -> Option<BlameLine> {
+        let commit = self.commit_for_version(report.get_application_info().get_version())?;
+        let path = self.resolve(&commit, location.file()?)?;
+        match location.line() {
+            Some(0) | None => None,
+            Some(line) => self.blame_line(&commit, &path, line),
+        }
+    }
+
+    fn tree(&self, commit: &str) -> Option<Rc<Vec<String>>> {
+        if let Some(tree) = self.trees.borrow().get(commit) {
+            return Some(tree.clone());
+        }
+        let output = git(&self.repo, &["ls-tree", "-r", "--name-only", "--full-tree", commit])?;
+        let tree = Rc::new(output.lines().map(|line| line.to_string()).collect::<Vec<_>>());
+        self.trees.borrow_mut().insert(commit.into(), tree.clone());
+        Some(tree)
+    }
+}
+
+/// Name likely owners and suspect commits from the blamed frames of a backtrace.
+pub fn summarize<'a, I>(frames: I, max_suspects: usize) -> BlameSummary
+    where I: IntoIterator<Item=&'a BacktraceFrame>
+{
+    let mut summary = BlameSummary::default();
+    for blame in frames.into_iter().filter_map(|frame| frame.blame.as_ref()) {
+        if let Some(owner) = summary.owners.iter_mut().find(|o| o.author_mail == blame.author_mail) {
+            owner.frames += 1;
+        } else {
+            summary.owners.push(Owner {
+                author: blame.author.clone(),
+                author_mail: blame.author_mail.clone(),
+                frames: 1,
+            });
+        }
+        if !summary.suspects.iter().any(|s| s.commit == blame.commit) {
+            summary.suspects.push(blame.clone());
+        }
+    }
+    /* Stable sorts, so ties keep the order of the backtrace */
+    summary.owners.sort_by_key(|owner| Reverse(owner.frames));
+    summary.suspects.sort_by_key(|suspect| Reverse(suspect.author_time));
+    summary.suspects.truncate(max_suspects);
+    summary
+}
+
+fn parse_porcelain(output: &str) -> Option<BlameLine> {
+    let mut lines = output.lines();
+    let commit = lines.next()?.split(' ').next()?.to_string();
+    let mut ret = BlameLine {
+        commit,
+        author: String::new(),
+        author_mail: String::new(),
+        author_time: 0,
+        summary: String::new(),
+    };
+    for line in lines {
+        /* The line contents come last and are prefixed with a tab */
+        if line.starts_with('\t') {
+            break;
+        }
+        let mut parts = line.splitn(2, ' ');
+        let key = parts.next().unwrap_or("");
+        let value = parts.next().unwrap_or("");
+        match key {
+            "author" => ret.author = value.into(),
+            "author-mail" => ret.author_mail = value.trim_matches(|c| c == '<' || c == '>').into(),
+            "author-time" => ret.author_time = value.parse().unwrap_or(0),
+            "summary" => ret.summary = value.into(),
+            _ => {},
+        }
+    }
+    Some(ret)
+}
+
+fn git(repo: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        None
+    }
+}
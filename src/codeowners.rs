@@ -0,0 +1,237 @@
+use std::path::{Component, Path};
+
+use failure::ResultExt;
+use regex::Regex;
+use serde::Serialize;
+
+use super::backtrace::BacktraceFrame;
+use super::culprit::{culprit, InAppRules};
+use super::protos::crash_report::CrashReport;
+use super::source::PathMap;
+use crate::error::{Error, ErrorKind};
+
+/// Prefix of rules that match the image of a frame instead of its source path, like `image:MetaZKit @media`.
+pub const IMAGE_RULE_PREFIX : &str = "image:";
+
+#[derive(Clone, Debug)]
+enum Matcher {
+    /// A path pattern, and whether it is anchored at the repository root.
+    Path(Regex, bool),
+    Image(String),
+}
+
+/// A line of a CODEOWNERS file.
+#[derive(Clone, Debug, Serialize)]
+pub struct OwnerRule {
+    /// Line number in the file, starting at 1.
+    pub line: usize,
+    pub pattern: String,
+    /// Teams or people, like `@org/media`.
+    pub owners: Vec<String>,
+    #[serde(skip)]
+    matcher: Matcher,
+}
+
+/// Turn a CODEOWNERS path pattern into a regex over `/` separated relative paths, noting whether it is anchored.
+///
+/// Like gitignore, patterns without a `/` other than a trailing one match at any depth, `*` stops at
+/// `/` while `**` doesn't, and a pattern matching a directory matches everything below it.
+fn path_matcher(pattern: &str) -> Result<Matcher, Error> {
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+
+    let mut regex = String::from("^");
+    if !anchored {
+        regex.push_str("(?:.*/)?");
+    }
+    let mut chars = trimmed.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            },
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push_str("(?:/.*)?$");
+    Ok(Matcher::Path(Regex::new(&regex).context(ErrorKind::Pattern(pattern.into()))?, anchored))
+}
+
+/// Owners of source paths and images, from a CODEOWNERS style file. The last matching rule wins.
+#[derive(Clone, Debug, Default)]
+pub struct CodeOwners {
+    rules: Vec<OwnerRule>,
+    paths: PathMap,
+}
+
+impl CodeOwners {
+    pub fn parse(text: &str) -> Result<CodeOwners, Error> {
+        let mut rules = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line_text = line.trim();
+            if line_text.is_empty() || line_text.starts_with('#') {
+                continue;
+            }
+            let mut fields = line_text.split_whitespace();
+            let pattern = fields.next().unwrap_or("");
+            let owners : Vec<String> = fields.take_while(|field| !field.starts_with('#')).map(String::from).collect();
+            let matcher = match pattern.strip_prefix(IMAGE_RULE_PREFIX) {
+                Some(image) => Matcher::Image(image.into()),
+                None => path_matcher(pattern)?,
+            };
+            rules.push(OwnerRule {
+                line: idx + 1,
+                pattern: pattern.into(),
+                owners,
+                matcher,
+            });
+        }
+        Ok(CodeOwners {
+            rules,
+            paths: PathMap::new(),
+        })
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<CodeOwners, Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).context(ErrorKind::Owners(path.to_path_buf()))?;
+        CodeOwners::parse(&text)
+    }
+
+    /// Rewrite DWARF paths to paths relative to the repository root.
+    pub fn set_path_map(&mut self, paths: PathMap) {
+        self.paths = paths;
+    }
+
+    pub fn rules(&self) -> &[OwnerRule] {
+        &self.rules
+    }
+
+    fn find<F: Fn(&Matcher) -> bool>(&self, matches: F) -> Option<&OwnerRule> {
+        self.rules.iter().rev().find(|rule| matches(&rule.matcher))
+    }
+
+    /// The rule owning a source path as recorded in DWARF.
+    ///
+    /// Paths that the path map doesn't rewrite are tried at every directory level, since the repository root
+    /// is not known. Patterns anchored at the root, like `/MetaZ/Plugins/`, are then only tried at the deepest level
+    /// one of them matches, which is taken as the root.
+    pub fn owner_of_path(&self, file: &str) -> Option<&OwnerRule> {
+        let path = Path::new(file);
+        if let Some(mapped) = self.paths.map(path) {
+            let mapped = mapped.to_string_lossy().replace('\\', "/");
+            return self.find(|matcher| match matcher {
+                Matcher::Path(regex, _) => regex.is_match(&mapped),
+                Matcher::Image(_) => false,
+            });
+        }
+
+        let components : Vec<String> = path.components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        let suffixes : Vec<String> = (0..components.len())
+            .map(|start| components[start..].join("/"))
+            .collect();
+        let root_suffix = suffixes.iter().rev().find(|suffix| {
+            self.rules.iter().any(|rule| match &rule.matcher {
+                Matcher::Path(regex, true) => regex.is_match(suffix),
+                _ => false,
+            })
+        });
+        self.find(|matcher| match matcher {
+            Matcher::Path(regex, false) => suffixes.iter().any(|suffix| regex.is_match(suffix)),
+            Matcher::Path(regex, true) => root_suffix.iter().any(|suffix| regex.is_match(suffix)),
+            Matcher::Image(_) => false,
+        })
+    }
+
+    /// The rule owning a frame, by its source path or else by its image.
+    pub fn owner_of_frame(&self, frame: &BacktraceFrame) -> Option<&OwnerRule> {
+        if let Some(rule) = frame.location.as_ref().and_then(|location| location.file()).and_then(|file| self.owner_of_path(file)) {
+            return Some(rule);
+        }
+        let image = frame.image_path.as_ref().map(|_| frame.image_name())?;
+        self.find(|matcher| match matcher {
+            Matcher::Image(name) => *name == image,
+            Matcher::Path(..) => false,
+        })
+    }
+}
+
+/// How to pick the owners of a report.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoutingStrategy {
+    /// The owners of the culprit frame.
+    Culprit,
+    /// Every owned in-app frame votes for its owners, the frame at position `n` among them with weight `1 / (n + 1)`.
+    Vote,
+}
+
+/// Total weight of the votes for one set of owners.
+#[derive(Clone, Debug, Serialize)]
+pub struct Vote {
+    pub owners: Vec<String>,
+    pub weight: f64,
+    /// Rules of the frames that voted, in frame order and without repeats.
+    pub rules: Vec<OwnerRule>,
+}
+
+/// Where a report should go and why.
+#[derive(Clone, Debug, Serialize)]
+pub struct Routing {
+    pub owners: Vec<String>,
+    /// The rule that matched `frame`. With `RoutingStrategy::Vote` other rules may have voted for the same
+    /// owners, the first of `votes` lists them all.
+    pub rule: OwnerRule,
+    /// Index of the highest frame owned by `owners`.
+    pub frame: usize,
+    /// All votes, highest first. Empty for `RoutingStrategy::Culprit`.
+    pub votes: Vec<Vote>,
+}
+
+/// Route a report to the owners of its crashed thread `frames`.
+pub fn route(frames: &[BacktraceFrame], report: &CrashReport, in_app: &InAppRules, owners: &CodeOwners, strategy: RoutingStrategy) -> Option<Routing> {
+    if strategy == RoutingStrategy::Culprit {
+        let frame = culprit(frames, report, in_app)?;
+        let rule = owners.owner_of_frame(frame)?;
+        return Some(Routing {
+            owners: rule.owners.clone(),
+            rule: rule.clone(),
+            frame: frame.index,
+            votes: Vec::new(),
+        });
+    }
+
+    let mut votes : Vec<(Vote, &OwnerRule, usize)> = Vec::new();
+    let owned = frames.iter()
+        .filter(|frame| in_app.is_in_app(report, frame))
+        .filter_map(|frame| owners.owner_of_frame(frame).map(|rule| (frame, rule)));
+    for (position, (frame, rule)) in owned.enumerate() {
+        let weight = 1.0 / (position + 1) as f64;
+        match votes.iter_mut().find(|vote| vote.0.owners == rule.owners) {
+            Some(vote) => {
+                vote.0.weight += weight;
+                if !vote.0.rules.iter().any(|voted| voted.line == rule.line) {
+                    vote.0.rules.push(rule.clone());
+                }
+            },
+            None => votes.push((Vote { owners: rule.owners.clone(), weight, rules: vec![rule.clone()] }, rule, frame.index)),
+        }
+    }
+    /* Stable sort, so ties go to the owners of the higher frame */
+    votes.sort_by(|a, b| b.0.weight.partial_cmp(&a.0.weight).unwrap_or(std::cmp::Ordering::Equal));
+    let (winner, rule, frame) = votes.first()?;
+    Some(Routing {
+        owners: winner.owners.clone(),
+        rule: (*rule).clone(),
+        frame: *frame,
+        votes: votes.iter().map(|vote| vote.0.clone()).collect(),
+    })
+}
@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs::File;
+use std::path::Path;
+
+use failure::ResultExt;
+use serde::Serialize;
+
+use super::fingerprint::{fingerprint, Fingerprint, FingerprintOptions};
+use super::options::Options;
+use super::protos::crash_report::CrashReport;
+use super::read_report;
+use super::symbolicate::Symbolicate;
+use crate::error::{Error, ErrorKind};
+
+/// All reports with the same fingerprint.
+#[derive(Clone, Debug, Serialize)]
+pub struct Group {
+    /// Fingerprint of the first report in the group.
+    pub fingerprint: Fingerprint,
+    pub count: u64,
+    /// Counts by `ApplicationInfo.version`.
+    pub versions: BTreeMap<String, u64>,
+    /// Counts by OS version and build, like `10.13.6 (17G65)`.
+    pub os_versions: BTreeMap<String, u64>,
+    /// Counts by `MachineInfo.model`.
+    pub models: BTreeMap<String, u64>,
+    /// Earliest and latest `SystemInfo.timestamp`, for reports that have one, in seconds since the epoch.
+    pub first_seen: Option<i64>,
+    pub last_seen: Option<i64>,
+}
+
+impl Group {
+    fn new(fingerprint: Fingerprint) -> Group {
+        Group {
+            fingerprint,
+            count: 0,
+            versions: BTreeMap::new(),
+            os_versions: BTreeMap::new(),
+            models: BTreeMap::new(),
+            first_seen: None,
+            last_seen: None,
+        }
+    }
+
+    fn add(&mut self, report: &CrashReport) {
+        self.count += 1;
+        *self.versions.entry(app_version(report)).or_insert(0) += 1;
+        *self.os_versions.entry(os_version(report)).or_insert(0) += 1;
+        *self.models.entry(model(report)).or_insert(0) += 1;
+
+        let timestamp = report.get_system_info().get_timestamp();
+        if timestamp > 0 {
+            self.first_seen = Some(self.first_seen.map_or(timestamp, |first| first.min(timestamp)));
+            self.last_seen = Some(self.last_seen.map_or(timestamp, |last| last.max(timestamp)));
+        }
+    }
+}
+
+/// `ApplicationInfo.version`, or `???`.
+pub fn app_version(report: &CrashReport) -> String {
+    let info = report.get_application_info();
+    if info.has_version() {
+        info.get_version().into()
+    } else {
+        String::from("???")
+    }
+}
+
+fn os_version(report: &CrashReport) -> String {
+    let info = report.get_system_info();
+    if info.has_os_build() {
+        format!("{} ({})", info.get_os_version(), info.get_os_build())
+    } else {
+        info.get_os_version().into()
+    }
+}
+
+fn model(report: &CrashReport) -> String {
+    if report.has_machine_info() && report.get_machine_info().has_model() {
+        report.get_machine_info().get_model().into()
+    } else {
+        String::from("???")
+    }
+}
+
+/// Many reports grouped by fingerprint.
+#[derive(Default)]
+pub struct Corpus {
+    pub fingerprint_options: FingerprintOptions,
+    groups: BTreeMap<String, Group>,
+    reports: u64,
+}
+
+impl Corpus {
+    pub fn new(fingerprint_options: FingerprintOptions) -> Corpus {
+        Corpus {
+            fingerprint_options,
+            groups: BTreeMap::new(),
+            reports: 0,
+        }
+    }
+
+    /// Add `report` to the group of its fingerprint, and return the group.
+    pub fn add(&mut self, report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<&Group, Error> {
+        let fingerprint = fingerprint(report, symbolicate, options, &self.fingerprint_options)?;
+        self.reports += 1;
+        let group = self.groups.entry(fingerprint.hash.clone())
+            .or_insert_with(|| Group::new(fingerprint));
+        group.add(report);
+        Ok(group)
+    }
+
+    /// Read the `.plcrash` file at `path` and add it.
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<&Group, Error> {
+        let path = path.as_ref();
+        let report = {
+            let mut file = File::open(path).context(ErrorKind::Report(path.to_path_buf()))?;
+            read_report(&mut file).context(ErrorKind::Report(path.to_path_buf()))?
+        };
+        self.add(&report, symbolicate, options)
+    }
+
+    /// Number of reports added.
+    pub fn reports(&self) -> u64 {
+        self.reports
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Group> {
+        self.groups.get(hash)
+    }
+
+    /// Groups with the most reports first.
+    pub fn groups(&self) -> Vec<&Group> {
+        let mut ret : Vec<&Group> = self.groups.values().collect();
+        ret.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.fingerprint.hash.cmp(&b.fingerprint.hash)));
+        ret
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        #[derive(Serialize)]
+        struct JsonCorpus<'a> {
+            reports: u64,
+            groups: Vec<&'a Group>,
+        }
+        let json = JsonCorpus {
+            reports: self.reports,
+            groups: self.groups(),
+        };
+        Ok(serde_json::to_string_pretty(&json).context(ErrorKind::Json)?)
+    }
+
+    /// One row per group. Breakdowns are written as `key=count` pairs separated by `;`, and timestamps in
+    /// seconds since the epoch like the JSON.
+    pub fn to_csv(&self) -> String {
+        let mut ret = String::from("hash,count,crash_type,exception_name,top_frame,first_seen,last_seen,versions,os_versions,models\n");
+        for group in self.groups() {
+            let fingerprint = &group.fingerprint;
+            let top_frame = fingerprint.frames.first()
+                .map(|frame| format!("{}`{}", frame.image, frame.function))
+                .unwrap_or_default();
+            let fields = [
+                fingerprint.hash.clone(),
+                group.count.to_string(),
+                fingerprint.crash_type.clone(),
+                fingerprint.exception_name.clone().unwrap_or_default(),
+                top_frame,
+                group.first_seen.map(|timestamp| timestamp.to_string()).unwrap_or_default(),
+                group.last_seen.map(|timestamp| timestamp.to_string()).unwrap_or_default(),
+                format_counts(&group.versions),
+                format_counts(&group.os_versions),
+                format_counts(&group.models),
+            ];
+            let row : Vec<String> = fields.iter().map(|field| csv_escape(field)).collect();
+            writeln!(ret, "{}", row.join(",")).unwrap();
+        }
+        ret
+    }
+}
+
+fn format_counts(counts: &BTreeMap<String, u64>) -> String {
+    let pairs : Vec<String> = counts.iter().map(|(key, count)| format!("{}={}", key, count)).collect();
+    pairs.join(";")
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
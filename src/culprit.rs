@@ -0,0 +1,92 @@
+use super::backtrace::BacktraceFrame;
+use super::glob::Glob;
+use super::image_kind::{ImageClassifier, ImageKind};
+use super::protos::crash_report::CrashReport;
+
+/// Decides which frames belong to the app.
+///
+/// Symbol patterns win over path rules, path rules win over the kind of image, and excludes win over includes.
+#[derive(Clone, Debug)]
+pub struct InAppRules {
+    /// The main executable, matched against `ProcessInfo.process_path`.
+    pub main_executable: bool,
+    /// Frameworks and libraries inside the `.app` bundle, other than known third-party SDKs.
+    pub app_bundle: bool,
+    /// When not empty, `app_bundle` only applies to apps whose `ApplicationInfo.identifier` starts with one of these.
+    /// Images don't carry their own bundle identifier.
+    pub bundle_prefixes: Vec<String>,
+    /// Image paths that are in-app, like `*.app/Frameworks/*`.
+    pub include_paths: Vec<Glob>,
+    /// Image paths that are never in-app, like `*/Frameworks/Sparkle.framework/*`.
+    pub exclude_paths: Vec<Glob>,
+    /// Frames whose symbol or function matches are in-app wherever they are.
+    pub include_symbols: Vec<Glob>,
+    /// Frames whose symbol or function matches are never in-app, like assertion helpers.
+    pub exclude_symbols: Vec<Glob>,
+    /// Classifies images of frames that don't carry their kind. Use the same SDKs as `Options.image_classifier`.
+    pub classifier: ImageClassifier,
+}
+
+impl Default for InAppRules {
+    fn default() -> InAppRules {
+        InAppRules {
+            main_executable: true,
+            app_bundle: true,
+            bundle_prefixes: Vec::new(),
+            include_paths: Vec::new(),
+            exclude_paths: Vec::new(),
+            include_symbols: Vec::new(),
+            exclude_symbols: Vec::new(),
+            classifier: ImageClassifier::default(),
+        }
+    }
+}
+
+impl InAppRules {
+    /// Whether the image at `image_path` is in-app, regardless of symbols.
+    pub fn is_in_app_image(&self, report: &CrashReport, image_path: &str) -> bool {
+        let kind = self.classifier.classify(report, image_path);
+        self.image_in_app(report, image_path, &kind)
+    }
+
+    /// Third-party SDKs, system and simulator images are only in-app when a path or symbol rule says so.
+    fn image_in_app(&self, report: &CrashReport, image_path: &str, kind: &ImageKind) -> bool {
+        if self.exclude_paths.iter().any(|glob| glob.is_match(image_path)) {
+            return false;
+        }
+        if self.include_paths.iter().any(|glob| glob.is_match(image_path)) {
+            return true;
+        }
+        match kind {
+            ImageKind::MainExecutable => self.main_executable,
+            ImageKind::AppFramework if self.app_bundle => {
+                let identifier = report.get_application_info().get_identifier();
+                self.bundle_prefixes.is_empty()
+                    || self.bundle_prefixes.iter().any(|prefix| identifier.starts_with(prefix.as_str()))
+            },
+            _ => false,
+        }
+    }
+
+    pub fn is_in_app(&self, report: &CrashReport, frame: &BacktraceFrame) -> bool {
+        let names : Vec<&String> = frame.symbol.iter().chain(frame.function.iter()).collect();
+        if names.iter().any(|name| self.exclude_symbols.iter().any(|glob| glob.is_match(name))) {
+            return false;
+        }
+        if names.iter().any(|name| self.include_symbols.iter().any(|glob| glob.is_match(name))) {
+            return true;
+        }
+        match (frame.image_path.as_ref(), frame.image_kind.as_ref()) {
+            (Some(path), Some(kind)) => self.image_in_app(report, path, kind),
+            (Some(path), None) => self.is_in_app_image(report, path),
+            (None, _) => false,
+        }
+    }
+}
+
+/// The first in-app frame of `frames`, the frame most likely responsible for the crash.
+///
+/// Frames above it are in system code like `objc_msgSend` or `__pthread_kill` and rarely where the bug is.
+pub fn culprit<'f>(frames: &'f [BacktraceFrame], report: &CrashReport, rules: &InAppRules) -> Option<&'f BacktraceFrame> {
+    frames.iter().find(|frame| rules.is_in_app(report, frame))
+}
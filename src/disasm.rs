@@ -0,0 +1,224 @@
+use capstone::prelude::*;
+use failure::ResultExt;
+use serde::Serialize;
+
+use super::arch::Arch;
+use super::backtrace::{image_for_address, image_uuid};
+use super::binary::Binaries;
+use super::fault;
+use super::protos::crash_report::{CrashReport, CrashReport_Thread_RegisterValue};
+use super::registers::{self, RegisterRole};
+use super::symbolicate::Symbolicate;
+use crate::error::{Error, ErrorKind};
+
+/// Number of instructions shown before and after the crashed pc.
+pub const DISASSEMBLY_INSTRUCTIONS : usize = 6;
+
+/// How far before the pc a linear sweep of x86 code may start.
+const MAX_SWEEP_BACK : u64 = 4096;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Instruction {
+    pub address: u64,
+    pub bytes: Vec<u8>,
+    pub mnemonic: String,
+    pub operands: String,
+    /// This is the instruction at the crashed pc.
+    pub faulting: bool,
+    /// Register values and computed addresses used by the operands of the faulting instruction.
+    pub annotations: Vec<String>,
+}
+
+/// Instructions around the pc of the crashed thread.
+#[derive(Clone, Debug, Serialize)]
+pub struct Disassembly {
+    /// Path of the image containing the pc.
+    pub image: String,
+    /// x86 code was decoded from the pc, since the start of the function is unknown, so nothing before it is shown.
+    pub starts_at_pc: bool,
+    pub instructions: Vec<Instruction>,
+}
+
+impl Disassembly {
+    pub fn faulting(&self) -> Option<&Instruction> {
+        self.instructions.iter().find(|instruction| instruction.faulting)
+    }
+}
+
+/// Disassemble around the pc of the crashed thread, for x86_64 and arm64 when the binary containing it is in `binaries`.
+pub fn disassemble(report: &CrashReport, binaries: &Binaries, symbolicate: Option<&Symbolicate>) -> Result<Option<Disassembly>, Error> {
+    let arch = Arch::of(report);
+    let thread = match report.get_threads().iter().find(|thread| thread.get_crashed()) {
+        Some(thread) => thread,
+        None => return Ok(None),
+    };
+    let pc = match registers::find_by_role(arch, thread, RegisterRole::ProgramCounter) {
+        Some(pc) => pc,
+        None => return Ok(None),
+    };
+    let image = match image_for_address(report, pc) {
+        Some(image) => image,
+        None => return Ok(None),
+    };
+    let binary = match image_uuid(image).and_then(|uuid| binaries.get(&uuid)) {
+        Some(binary) => binary,
+        None => return Ok(None),
+    };
+    let base = image.get_base_address();
+    let offset = pc - base;
+    let kind = ErrorKind::Disassemble(binary.path.clone(), offset);
+
+    /* arm64 instructions are all 4 bytes, but x86 has to be decoded from a known instruction boundary */
+    let start = if arch == Arch::Arm64 {
+        offset.saturating_sub(4 * DISASSEMBLY_INSTRUCTIONS as u64)
+    } else {
+        let lookup = image_uuid(image).and_then(|uuid| symbolicate.and_then(|s| s.get(&uuid)));
+        let function_start = lookup
+            .and_then(|lookup| lookup.function_range(offset).map(|range| range.0).or_else(|| lookup.find_symbol(offset).map(|symbol| symbol.1)))
+            .or_else(|| {
+                let frame = thread.get_frames().first()?;
+                if frame.has_symbol() {
+                    frame.get_symbol().get_start_address().checked_sub(base)
+                } else {
+                    None
+                }
+            });
+        match function_start {
+            Some(start) if start <= offset && offset - start <= MAX_SWEEP_BACK => start,
+            _ => offset,
+        }
+    };
+
+    let bytes = match binary.read(start, offset - start + 16 * (DISASSEMBLY_INSTRUCTIONS as u64 + 1)) {
+        Some(bytes) => bytes,
+        None => return Ok(None),
+    };
+    let fault_address = fault::classify(report, symbolicate).map(|fault| fault.address);
+    let instructions = match decode(arch, bytes, base + start, pc, thread.get_registers(), fault_address).context(kind)? {
+        Some(instructions) => instructions,
+        None => return Ok(None),
+    };
+
+    Ok(Some(Disassembly {
+        image: image.get_name().into(),
+        starts_at_pc: arch != Arch::Arm64 && start == offset,
+        instructions,
+    }))
+}
+
+/// Decode `bytes`, loaded at `address`, and keep the instructions around `pc`.
+///
+/// The operands of the instruction at `pc` are annotated with the values in `thread_registers`. None when we
+/// don't disassemble `arch`, or no instruction starts at `pc`.
+pub fn decode(arch: Arch, bytes: &[u8], address: u64, pc: u64, thread_registers: &[CrashReport_Thread_RegisterValue], fault_address: Option<u64>) -> Result<Option<Vec<Instruction>>, capstone::Error> {
+    let cs = match arch {
+        Arch::X86_64 => Capstone::new().x86().mode(arch::x86::ArchMode::Mode64).build()?,
+        Arch::Arm64 => Capstone::new().arm64().mode(arch::arm64::ArchMode::Arm).build()?,
+        _ => return Ok(None),
+    };
+    let insns = cs.disasm_all(bytes, address)?;
+    let decoded : Vec<_> = insns.iter().collect();
+    let idx = match decoded.iter().position(|insn| insn.address() == pc) {
+        Some(idx) => idx,
+        None => return Ok(None),
+    };
+
+    let first = idx.saturating_sub(DISASSEMBLY_INSTRUCTIONS);
+    let last = (idx + DISASSEMBLY_INSTRUCTIONS + 1).min(decoded.len());
+    let instructions = decoded[first..last].iter()
+        .map(|insn| {
+            let operands = insn.op_str().unwrap_or("").to_string();
+            let faulting = insn.address() == pc;
+            let annotations = if faulting {
+                annotate_operands(&operands, thread_registers, arch, fault_address)
+            } else {
+                Vec::new()
+            };
+            Instruction {
+                address: insn.address(),
+                bytes: insn.bytes().to_vec(),
+                mnemonic: insn.mnemonic().unwrap_or("").to_string(),
+                operands,
+                faulting,
+                annotations,
+            }
+        })
+        .collect();
+    Ok(Some(instructions))
+}
+
+/// The value of a register as named by the disassembler, including the narrower views of registers.
+fn register_value(thread_registers: &[CrashReport_Thread_RegisterValue], arch: Arch, name: &str) -> Option<u64> {
+    let (full, mask) = if arch == Arch::Arm64 {
+        match name {
+            "x29" => ("fp".to_string(), !0),
+            "x30" => ("lr".to_string(), !0),
+            _ if name.starts_with('w') => (format!("x{}", &name[1..]), 0xffff_ffff),
+            _ => (name.to_string(), !0),
+        }
+    } else if name.len() == 3 && name.starts_with('e') {
+        (format!("r{}", &name[1..]), 0xffff_ffff)
+    } else if name.starts_with('r') && name.ends_with('d') {
+        (name[..name.len() - 1].to_string(), 0xffff_ffff)
+    } else {
+        (name.to_string(), !0)
+    };
+    let value = match full.as_str() {
+        "fp" => registers::find(thread_registers, &["fp", "x29"]),
+        "lr" => registers::find(thread_registers, &["lr", "x30"]),
+        full => registers::find(thread_registers, &[full]),
+    };
+    value.map(|value| value & mask)
+}
+
+fn parse_immediate(token: &str) -> Option<i64> {
+    let token = token.trim_start_matches('#');
+    let (negative, token) = match token.strip_prefix('-') {
+        Some(token) => (true, token),
+        None => (false, token),
+    };
+    let value = match token.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => token.parse().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+/// Annotate the registers used by the operands, and a memory operand whose address is the fault address.
+fn annotate_operands(operands: &str, thread_registers: &[CrashReport_Thread_RegisterValue], arch: Arch, fault_address: Option<u64>) -> Vec<String> {
+    let mut ret = Vec::new();
+    let is_token_char = |c: char| c.is_ascii_alphanumeric() || c == '#' || c == '-';
+    for token in operands.split(|c: char| !is_token_char(c)).filter(|token| !token.is_empty()) {
+        if let Some(value) = register_value(thread_registers, arch, token) {
+            let annotation = format!("{} = {:#x}", token, value);
+            if !ret.contains(&annotation) {
+                ret.push(annotation);
+            }
+        }
+    }
+
+    /* A simple base + displacement memory operand, like [rdi + 0x18] or [x0, #0x18] */
+    if let (Some(open), Some(close), Some(fault_address)) = (operands.find('['), operands.find(']'), fault_address) {
+        if open < close && !operands[open..close].contains('*') {
+            let memory = &operands[open + 1..close];
+            let mut base = None;
+            let mut displacement = 0i64;
+            let mut negate = false;
+            for token in memory.split(&[',', ' '][..]).filter(|token| !token.is_empty()) {
+                if token == "-" {
+                    negate = true;
+                } else if let Some(value) = register_value(thread_registers, arch, token) {
+                    base = base.or(Some(value));
+                } else if let Some(value) = parse_immediate(token) {
+                    displacement += if negate { -value } else { value };
+                }
+            }
+            if let Some(base) = base {
+                if base.wrapping_add(displacement as u64) == fault_address {
+                    ret.push(format!("[{}] is the fault address {:#x}", memory.trim(), fault_address));
+                }
+            }
+        }
+    }
+    ret
+}
@@ -22,6 +22,24 @@ pub enum ErrorKind {
     Zip(PathBuf),
     #[fail(display = "error looking up {:x} in {:?}", _1, _0)]
     Probe(PathBuf, u64),
+    #[fail(display = "error writing JSON report")]
+    Json,
+    #[fail(display = "{:?} is not a git repository", _0)]
+    Git(PathBuf),
+    #[fail(display = "invalid pattern {:?}", _0)]
+    Pattern(String),
+    #[fail(display = "error reading binary {:?}", _0)]
+    Binary(PathBuf),
+    #[fail(display = "error disassembling {:x} in {:?}", _1, _0)]
+    Disassemble(PathBuf, u64),
+    #[fail(display = "error reading report {:?}", _0)]
+    Report(PathBuf),
+    #[fail(display = "error reading code owners {:?}", _0)]
+    Owners(PathBuf),
+    #[fail(display = "invalid triage rules")]
+    Rules,
+    #[fail(display = "error reading triage rules {:?}", _0)]
+    RulesFile(PathBuf),
 }
 
 impl Fail for Error {
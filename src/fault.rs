@@ -0,0 +1,162 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use super::arch::Arch;
+use super::backtrace::{image_for_address, image_uuid, strip_symbol_prefix};
+use super::mach_exception::{MachException, EXC_BAD_ACCESS};
+use super::protos::crash_report::CrashReport;
+use super::registers::{self, RegisterRole};
+use super::signal::{Signal, SignalKind};
+use super::symbolicate::Symbolicate;
+
+/// Addresses below this are taken to be a field or element access through a NULL pointer.
+pub const NULL_OFFSET_LIMIT : u64 = 0x4000;
+
+/// Addresses this close to the stack pointer are taken to be on the stack or its guard page.
+pub const STACK_DISTANCE_LIMIT : u64 = 0x10000;
+
+/// Where a faulting address points.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FaultKind {
+    /// The address is 0.
+    Null,
+    /// A small offset from 0, likely a field access on nil.
+    NearNull { offset: u64 },
+    /// Inside a loaded image.
+    Image {
+        image: String,
+        offset: u64,
+        segment: Option<String>,
+        section: Option<String>,
+        symbol: Option<String>,
+        symbol_offset: Option<u64>,
+    },
+    /// Close to the stack pointer of the crashed thread, likely a stack overflow.
+    Stack { stack_pointer: u64, distance: i64 },
+    /// An arm64 pointer with authentication bits left in it.
+    PointerAuthentication { stripped: u64 },
+    /// Anything else.
+    Wild,
+}
+
+/// A classified memory access fault.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fault {
+    pub address: u64,
+    pub kind: FaultKind,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            FaultKind::Null => write!(f, "NULL dereference"),
+            FaultKind::NearNull { offset } => write!(f, "NULL + {:#x}, likely a field access on a nil pointer", offset),
+            FaultKind::Image { ref image, offset, ref segment, ref section, ref symbol, symbol_offset } => {
+                write!(f, "{:#x} is inside {} at offset {:#x}", self.address, image, offset)?;
+                match (segment, section) {
+                    (Some(segment), Some(section)) => write!(f, " ({},{})", segment, section)?,
+                    (Some(segment), None) => write!(f, " ({})", segment)?,
+                    _ => {},
+                }
+                if let Some(symbol) = symbol {
+                    write!(f, " in {} + {}", symbol, symbol_offset.unwrap_or(0))?;
+                }
+                Ok(())
+            },
+            FaultKind::Stack { distance, .. } if distance < 0 => {
+                write!(f, "{:#x} is {:#x} below the stack pointer, likely a stack overflow", self.address, -distance)
+            },
+            FaultKind::Stack { distance, .. } => {
+                write!(f, "{:#x} is {:#x} above the stack pointer", self.address, distance)
+            },
+            FaultKind::PointerAuthentication { stripped } => {
+                write!(f, "pointer authentication failure, {:#x} is {:#x} with a signature", self.address, stripped)
+            },
+            FaultKind::Wild => write!(f, "wild pointer {:#x}", self.address),
+        }
+    }
+}
+
+/// The stack pointer of the crashed thread.
+fn stack_pointer(report: &CrashReport, arch: Arch) -> Option<u64> {
+    let thread = report.get_threads().iter().find(|thread| thread.get_crashed())?;
+    registers::find_by_role(arch, thread, RegisterRole::StackPointer)
+}
+
+/// Whether the address has bits set above the 47 bit user address space, as a signed arm64 pointer does.
+fn is_signed_pointer(address: u64) -> bool {
+    let high = address >> 47;
+    high != 0 && high != 0x1_ffff
+}
+
+/// Classify the faulting address of `report`, if it crashed on a memory access.
+pub fn classify(report: &CrashReport, symbolicate: Option<&Symbolicate>) -> Option<Fault> {
+    let signal_info = report.get_signal();
+    let arch = Arch::of(report);
+    let address = if signal_info.has_mach_exception() {
+        let exception = MachException::new(signal_info.get_mach_exception(), arch);
+        if exception.exception_type != EXC_BAD_ACCESS {
+            return None;
+        }
+        exception.fault_address().unwrap_or_else(|| signal_info.get_address())
+    } else {
+        match Signal::new(signal_info).kind {
+            SignalKind::Sigsegv | SignalKind::Sigbus => signal_info.get_address(),
+            _ => return None,
+        }
+    };
+    Some(Fault {
+        address,
+        kind: classify_address(report, address, arch, symbolicate),
+    })
+}
+
+fn classify_address(report: &CrashReport, address: u64, arch: Arch, symbolicate: Option<&Symbolicate>) -> FaultKind {
+    if address == 0 {
+        return FaultKind::Null;
+    }
+    if address < NULL_OFFSET_LIMIT {
+        return FaultKind::NearNull { offset: address };
+    }
+    if arch == Arch::Arm64 && is_signed_pointer(address) {
+        return FaultKind::PointerAuthentication { stripped: address & 0x7fff_ffff_ffff };
+    }
+
+    if let Some(image) = image_for_address(report, address) {
+        let offset = address - image.get_base_address();
+        let (mut segment, mut section, mut symbol, mut symbol_offset) = (None, None, None, None);
+        let lookup = image_uuid(image).and_then(|uuid| symbolicate.and_then(|s| s.get(&uuid)));
+        if let Some(lookup) = lookup {
+            if let Some(region) = lookup.find_section(offset) {
+                segment = Some(region.segment.clone());
+                section = region.section.clone();
+            } else if let Some(region) = lookup.segments().iter().find(|r| r.start <= offset && offset < r.end) {
+                segment = Some(region.segment.clone());
+            }
+            if let Some((name, start)) = lookup.find_symbol(offset) {
+                symbol = Some(strip_symbol_prefix(name, report).into());
+                symbol_offset = Some(offset - start);
+            }
+        }
+        return FaultKind::Image {
+            image: image.get_name().into(),
+            offset,
+            segment,
+            section,
+            symbol,
+            symbol_offset,
+        };
+    }
+
+    if let Some(stack_pointer) = stack_pointer(report, arch) {
+        let above = address.wrapping_sub(stack_pointer);
+        let below = stack_pointer.wrapping_sub(address);
+        if above.min(below) <= STACK_DISTANCE_LIMIT {
+            return FaultKind::Stack { stack_pointer, distance: above as i64 };
+        }
+    }
+
+    FaultKind::Wild
+}
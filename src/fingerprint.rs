@@ -0,0 +1,186 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use super::arch::Arch;
+use super::backtrace::{symbolicate_frames, BacktraceFrame};
+use super::culprit::InAppRules;
+use super::image_kind::ImageKind;
+use super::mach_exception::MachException;
+use super::options::Options;
+use super::protos::crash_report::CrashReport;
+use super::reason::ReasonNormalizer;
+use super::signal::Signal;
+use super::symbolicate::Symbolicate;
+use crate::error::Error;
+
+/// Settings for `fingerprint`.
+#[derive(Clone, Debug)]
+pub struct FingerprintOptions {
+    /// Number of frames that go into the fingerprint.
+    pub frames: usize,
+    /// Include the name of an uncaught exception, like `NSRangeException`.
+    pub exception_name: bool,
+    /// Include the reason of an uncaught exception, normalized so that reasons differing only in addresses,
+    /// numbers or literals get the same fingerprint.
+    pub exception_reason: Option<ReasonNormalizer>,
+    /// Use the backtrace of an uncaught exception instead of the crashed thread when there is one.
+    pub exception_backtrace: bool,
+    /// Use the top system frames when there are no in-app frames with a name.
+    pub system_fallback: bool,
+    /// Which frames count as in-app.
+    pub in_app: InAppRules,
+}
+
+impl Default for FingerprintOptions {
+    fn default() -> FingerprintOptions {
+        FingerprintOptions {
+            frames: 5,
+            exception_name: true,
+            exception_reason: Some(ReasonNormalizer::default()),
+            exception_backtrace: true,
+            system_fallback: true,
+            in_app: InAppRules::default(),
+        }
+    }
+}
+
+/// A frame that went into a fingerprint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct FingerprintFrame {
+    /// Position of the frame in the backtrace it was taken from.
+    pub index: usize,
+    /// File name of the image.
+    pub image: String,
+    /// Function or symbol name, without the offset.
+    pub function: String,
+    pub image_kind: Option<ImageKind>,
+    pub in_app: bool,
+}
+
+/// A stable signature for grouping reports of the same crash.
+///
+/// Only names go into the hash, so the same crash gets the same fingerprint across app versions and
+/// regardless of where the images were loaded.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Fingerprint {
+    /// Hex encoded hash of `components`.
+    pub hash: String,
+    /// Normalized crash type, like `EXC_BAD_ACCESS/SIGSEGV/SEGV_MAPERR`.
+    pub crash_type: String,
+    pub exception_name: Option<String>,
+    /// Normalized exception reason.
+    pub exception_reason: Option<String>,
+    /// Frames that contributed, topmost first.
+    pub frames: Vec<FingerprintFrame>,
+    /// Whether `frames` came from the uncaught exception backtrace.
+    pub from_exception: bool,
+}
+
+impl Fingerprint {
+    /// The strings that were hashed, in order.
+    pub fn components(&self) -> Vec<String> {
+        let mut ret = vec![self.crash_type.clone()];
+        if let Some(name) = self.exception_name.as_ref() {
+            ret.push(name.clone());
+        }
+        if let Some(reason) = self.exception_reason.as_ref() {
+            ret.push(reason.clone());
+        }
+        for frame in &self.frames {
+            ret.push(format!("{}`{}", frame.image, frame.function));
+        }
+        ret
+    }
+}
+
+/// 64 bit FNV-1a. It is fixed by its definition, unlike the hashers in std.
+fn fnv1a(components: &[String]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for component in components {
+        for byte in component.bytes().chain(Some(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// The signal or Mach exception of `report` as a single string, with the code in its named form.
+pub fn crash_type(report: &CrashReport) -> String {
+    let signal_info = report.get_signal();
+    let signal = Signal::new(signal_info);
+    let mut ret = String::new();
+    if signal_info.has_mach_exception() {
+        let exception = MachException::new(signal_info.get_mach_exception(), Arch::of(report));
+        if let Some(name) = exception.name() {
+            write!(ret, "{}/", name).unwrap();
+        }
+    }
+    ret.push_str(signal.kind.name());
+    if let Some(code_name) = signal.code_name {
+        write!(ret, "/{}", code_name).unwrap();
+    }
+    ret
+}
+
+/// The name of a frame without addresses or offsets, if it has one.
+fn frame_function(frame: &BacktraceFrame) -> Option<String> {
+    frame.function.clone().or_else(|| frame.symbol.clone())
+}
+
+/// Compute the fingerprint of `report`.
+pub fn fingerprint(report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options, fingerprint_options: &FingerprintOptions) -> Result<Fingerprint, Error> {
+    let exception_frames = if report.has_exception() { report.get_exception().get_frames() } else { &[] };
+    let from_exception = fingerprint_options.exception_backtrace && !exception_frames.is_empty();
+    let frames = if from_exception {
+        symbolicate_frames(exception_frames, report, symbolicate, options)?
+    } else {
+        match report.get_threads().iter().find(|thread| thread.get_crashed()) {
+            Some(thread) => symbolicate_frames(thread.get_frames(), report, symbolicate, options)?,
+            None => Vec::new(),
+        }
+    };
+
+    let named : Vec<FingerprintFrame> = frames.iter()
+        .filter_map(|frame| {
+            let function = frame_function(frame)?;
+            let in_app = fingerprint_options.in_app.is_in_app(report, frame);
+            Some(FingerprintFrame {
+                index: frame.index,
+                image: frame.image_name(),
+                function,
+                image_kind: frame.image_kind.clone(),
+                in_app,
+            })
+        })
+        .collect();
+    let mut contributing : Vec<FingerprintFrame> = named.iter()
+        .filter(|frame| frame.in_app)
+        .take(fingerprint_options.frames)
+        .cloned()
+        .collect();
+    if contributing.is_empty() && fingerprint_options.system_fallback {
+        contributing = named.into_iter().take(fingerprint_options.frames).collect();
+    }
+
+    let exception_name = if fingerprint_options.exception_name && report.has_exception() {
+        Some(report.get_exception().get_name().to_string())
+    } else {
+        None
+    };
+    let exception_reason = match fingerprint_options.exception_reason.as_ref() {
+        Some(normalizer) if report.has_exception() => Some(normalizer.normalize(report.get_exception().get_reason())),
+        _ => None,
+    };
+    let mut ret = Fingerprint {
+        hash: String::new(),
+        crash_type: crash_type(report),
+        exception_name,
+        exception_reason,
+        frames: contributing,
+        from_exception,
+    };
+    ret.hash = format!("{:016x}", fnv1a(&ret.components()));
+    Ok(ret)
+}
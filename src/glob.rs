@@ -0,0 +1,38 @@
+use failure::ResultExt;
+use regex::Regex;
+
+use crate::error::{Error, ErrorKind};
+
+/// A shell style pattern, where `*` matches any run of characters including `/` and `?` matches one character.
+#[derive(Clone, Debug)]
+pub struct Glob {
+    pattern: String,
+    regex: Regex,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Glob, Error> {
+        let mut regex = String::from("^");
+        for c in pattern.chars() {
+            match c {
+                '*' => regex.push_str(".*"),
+                '?' => regex.push('.'),
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        regex.push('$');
+        Ok(Glob {
+            pattern: pattern.into(),
+            regex: Regex::new(&regex).context(ErrorKind::Pattern(pattern.into()))?,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Whether the whole of `text` matches.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
@@ -0,0 +1,251 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use super::arch::Arch;
+use super::backtrace::BacktraceFrame;
+use super::mach_exception::MachException;
+use super::protos::crash_report::*;
+use super::registers::{self, RegisterRole};
+
+/// Number of frames from the top of a thread that are looked at to classify it.
+pub const HANG_FRAMES : usize = 12;
+
+/// The exception code of a termination by the iOS watchdog.
+pub const WATCHDOG_CODE : u64 = 0x8bad_f00d;
+
+/// What a thread is doing.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadActivity {
+    /// Waiting for a pthread mutex or an `os_unfair_lock`.
+    MutexWait,
+    /// Waiting for a pthread read-write lock.
+    RwLockWait,
+    /// Waiting on a pthread condition variable.
+    ConditionWait,
+    /// Waiting on a Mach or dispatch semaphore.
+    Semaphore,
+    /// Waiting for a block submitted with `dispatch_sync` to run.
+    DispatchSync,
+    /// Waiting for a Mach message outside of a run loop.
+    MachMessage,
+    /// A run loop waiting for work.
+    RunLoopIdle,
+    /// A dispatch worker thread waiting for work.
+    WorkerIdle,
+    /// Waiting for I/O in `select`, `poll`, `kevent` or `read`.
+    Io,
+    /// Sleeping.
+    Sleep,
+    /// Running code outside the kernel.
+    Busy,
+    /// The top frames have no names.
+    Unknown,
+}
+
+impl ThreadActivity {
+    /// Waiting for something that another thread is expected to release.
+    pub fn is_blocked(self) -> bool {
+        matches!(self,
+            ThreadActivity::MutexWait |
+            ThreadActivity::RwLockWait |
+            ThreadActivity::ConditionWait |
+            ThreadActivity::Semaphore |
+            ThreadActivity::DispatchSync |
+            ThreadActivity::MachMessage |
+            ThreadActivity::Io |
+            ThreadActivity::Sleep)
+    }
+
+    /// Waiting for a lock or a queue that another thread holds.
+    pub fn is_lock_wait(self) -> bool {
+        matches!(self, ThreadActivity::MutexWait | ThreadActivity::RwLockWait | ThreadActivity::DispatchSync)
+    }
+}
+
+impl fmt::Display for ThreadActivity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            ThreadActivity::MutexWait => "waiting for a mutex",
+            ThreadActivity::RwLockWait => "waiting for a read-write lock",
+            ThreadActivity::ConditionWait => "waiting on a condition variable",
+            ThreadActivity::Semaphore => "waiting on a semaphore",
+            ThreadActivity::DispatchSync => "waiting in dispatch_sync",
+            ThreadActivity::MachMessage => "waiting for a Mach message",
+            ThreadActivity::RunLoopIdle => "run loop idle",
+            ThreadActivity::WorkerIdle => "idle worker",
+            ThreadActivity::Io => "waiting for I/O",
+            ThreadActivity::Sleep => "sleeping",
+            ThreadActivity::Busy => "running",
+            ThreadActivity::Unknown => "unknown",
+        };
+        f.write_str(description)
+    }
+}
+
+/// What one thread is doing, and the frame that tells.
+#[derive(Clone, Debug, Serialize)]
+pub struct ThreadSummary {
+    pub thread: u32,
+    pub crashed: bool,
+    pub activity: ThreadActivity,
+    /// Index of the frame the activity was decided by.
+    pub frame: Option<usize>,
+    /// Name of that frame.
+    pub symbol: Option<String>,
+    /// The lock or semaphore waited for, from the argument register of the blocking call, when the report has it.
+    pub wait_object: Option<u64>,
+}
+
+/// Threads blocked on locks or queues while no other thread runs.
+///
+/// This is where a lock-ordering deadlock would show, but it is not deadlock detection. Finding one needs the locks
+/// each thread holds, and the owner of a mutex or unfair lock is only recorded in process memory, which reports
+/// don't have. So no wait cycle can be found, and ordinary contention, like a `dispatch_sync` to a queue that a
+/// blocked thread is about to drain, looks the same. Take it as the threads to look at first.
+#[derive(Clone, Debug, Serialize)]
+pub struct LockWaits {
+    pub threads: Vec<u32>,
+    /// Distinct locks waited for, when the report has the registers of the waiting threads.
+    pub locks: Vec<u64>,
+}
+
+/// What every thread of a hang report is blocked on.
+#[derive(Clone, Debug, Serialize)]
+pub struct HangAnalysis {
+    pub threads: Vec<ThreadSummary>,
+    pub main_thread_blocked: bool,
+    pub lock_waits: Option<LockWaits>,
+}
+
+/// Whether `report` was taken on request or for a watchdog termination, rather than for a crash.
+///
+/// Other `SIGKILL`s, like jetsam or `kill -9`, don't count, since the threads weren't stuck.
+pub fn is_hang_report(report: &CrashReport) -> bool {
+    if report.get_report_info().get_user_requested() {
+        return true;
+    }
+    let signal_info = report.get_signal();
+    signal_info.has_mach_exception()
+        && MachException::new(signal_info.get_mach_exception(), Arch::of(report)).codes.contains(&WATCHDOG_CODE)
+}
+
+fn frame_name(frame: &BacktraceFrame) -> Option<&str> {
+    frame.symbol.as_ref().or(frame.function.as_ref()).map(|name| name.as_str())
+}
+
+/// System calls and traps that block, with what a thread in them is doing and the argument holding the lock or
+/// semaphore waited for. `__ulock_wait` takes an operation code before the address.
+const BLOCKING_CALLS : &[(&str, ThreadActivity, Option<usize>)] = &[
+    ("__psynch_mutexwait", ThreadActivity::MutexWait, Some(0)),
+    ("__ulock_wait", ThreadActivity::MutexWait, Some(1)),
+    ("__ulock_wait2", ThreadActivity::MutexWait, Some(1)),
+    ("__psynch_rw_rdlock", ThreadActivity::RwLockWait, Some(0)),
+    ("__psynch_rw_wrlock", ThreadActivity::RwLockWait, Some(0)),
+    ("__psynch_cvwait", ThreadActivity::ConditionWait, None),
+    ("semaphore_wait_trap", ThreadActivity::Semaphore, Some(0)),
+    ("semaphore_timedwait_trap", ThreadActivity::Semaphore, Some(0)),
+    ("mach_msg_trap", ThreadActivity::MachMessage, None),
+    ("mach_msg2_trap", ThreadActivity::MachMessage, None),
+    ("mach_msg_overwrite_trap", ThreadActivity::MachMessage, None),
+    ("__workq_kernreturn", ThreadActivity::WorkerIdle, None),
+    ("__select", ThreadActivity::Io, None),
+    ("__select_nocancel", ThreadActivity::Io, None),
+    ("poll", ThreadActivity::Io, None),
+    ("kevent", ThreadActivity::Io, None),
+    ("kevent64", ThreadActivity::Io, None),
+    ("kevent_qos", ThreadActivity::Io, None),
+    ("kevent_id", ThreadActivity::Io, None),
+    ("read", ThreadActivity::Io, None),
+    ("__read_nocancel", ThreadActivity::Io, None),
+    ("__semwait_signal", ThreadActivity::Sleep, None),
+    ("nanosleep", ThreadActivity::Sleep, None),
+];
+
+/// Frames that show that a lower level wait is part of a `dispatch_sync`.
+const DISPATCH_SYNC_MARKERS : &[&str] = &["__DISPATCH_WAIT_FOR_QUEUE__", "_dispatch_sync_f_slow", "_dispatch_sync_wait", "dispatch_sync"];
+
+/// Frames that show that a Mach message wait is a run loop waiting for work.
+const RUN_LOOP_MARKERS : &[&str] = &["__CFRunLoopServiceMachPort", "__CFRunLoopRun", "CFRunLoopRunSpecific"];
+
+fn classify_frames(frames: &[BacktraceFrame]) -> (ThreadActivity, Option<usize>) {
+    let top = &frames[..frames.len().min(HANG_FRAMES)];
+    let has = |markers: &[&str]| top.iter().filter_map(frame_name).any(|name| markers.contains(&name));
+
+    let first_named = top.iter().position(|frame| frame_name(frame).is_some());
+    let idx = match first_named {
+        Some(idx) => idx,
+        None => return (ThreadActivity::Unknown, None),
+    };
+    let name = frame_name(&top[idx]).unwrap_or("");
+    let activity = match BLOCKING_CALLS.iter().find(|call| call.0 == name) {
+        Some(&(_, activity, _)) => activity,
+        /* Frames above the first name are in images without symbols, which is most likely app code */
+        None => return (ThreadActivity::Busy, Some(idx)),
+    };
+    let activity = match activity {
+        ThreadActivity::MutexWait | ThreadActivity::Semaphore if has(DISPATCH_SYNC_MARKERS) => ThreadActivity::DispatchSync,
+        ThreadActivity::MachMessage if has(RUN_LOOP_MARKERS) => ThreadActivity::RunLoopIdle,
+        activity => activity,
+    };
+    (activity, Some(idx))
+}
+
+/// The lock or semaphore a thread stopped in the blocking call `name` waits for.
+fn wait_object(report: &CrashReport, thread: &CrashReport_Thread, name: &str) -> Option<u64> {
+    let argument = BLOCKING_CALLS.iter().find(|call| call.0 == name)?.2?;
+    registers::find_by_role(Arch::of(report), thread, RegisterRole::Argument(argument))
+}
+
+/// Classify what every thread of `report` is doing.
+///
+/// Two or more threads waiting for locks or queues with nothing running to release them are listed in
+/// `lock_waits`. Lock-ordering deadlocks can't be told apart from other waits, see `LockWaits`.
+///
+/// `frames` are the symbolicated backtraces of the threads, in the order of `CrashReport.threads`.
+pub fn analyze(report: &CrashReport, frames: &[Vec<BacktraceFrame>]) -> HangAnalysis {
+    let mut threads = Vec::new();
+    for (thread, frames) in report.get_threads().iter().zip(frames) {
+        let (activity, frame) = classify_frames(frames);
+        let symbol = frame.and_then(|idx| frame_name(&frames[idx]));
+        /* Registers are only those of the call when it is the top frame */
+        let wait_object = match (frame, symbol) {
+            (Some(0), Some(symbol)) => wait_object(report, thread, symbol),
+            _ => None,
+        };
+        threads.push(ThreadSummary {
+            thread: thread.get_thread_number(),
+            crashed: thread.get_crashed(),
+            activity,
+            frame,
+            symbol: symbol.map(String::from),
+            wait_object,
+        });
+    }
+
+    let main_thread_blocked = threads.iter()
+        .find(|state| state.thread == 0)
+        .map(|state| state.activity.is_blocked())
+        .unwrap_or(false);
+
+    let waiting : Vec<&ThreadSummary> = threads.iter().filter(|state| state.activity.is_lock_wait()).collect();
+    let busy = threads.iter().any(|state| state.activity == ThreadActivity::Busy && !state.crashed);
+    let lock_waits = if waiting.len() >= 2 && !busy {
+        let mut locks : Vec<u64> = waiting.iter().filter_map(|state| state.wait_object).collect();
+        locks.sort();
+        locks.dedup();
+        Some(LockWaits {
+            threads: waiting.iter().map(|state| state.thread).collect(),
+            locks,
+        })
+    } else {
+        None
+    };
+
+    HangAnalysis {
+        threads,
+        main_thread_blocked,
+        lock_waits,
+    }
+}
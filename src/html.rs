@@ -0,0 +1,95 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use super::backtrace::BacktraceFrame;
+use super::options::Options;
+use super::protos::crash_report::CrashReport;
+use super::symbolicate::Symbolicate;
+use super::text::{render_report, Backtraces, Markup};
+use crate::error::Error;
+
+/// Render the Apple style report as a standalone HTML page, with symbolicated locations linked to source hosting.
+pub fn html_report(report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<String, Error> {
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>").unwrap();
+    writeln!(html, "<html>").unwrap();
+    writeln!(html, "<head><meta charset=\"utf-8\"><title>Crash Report</title></head>").unwrap();
+    writeln!(html, "<body>").unwrap();
+    writeln!(html, "<pre>").unwrap();
+    let backtraces = Backtraces::new(report, symbolicate, options)?;
+    html.push_str(&render_report(report, symbolicate, options, &backtraces, Markup::Html)?);
+    writeln!(html, "</pre>").unwrap();
+    writeln!(html, "</body>").unwrap();
+    writeln!(html, "</html>").unwrap();
+    Ok(html)
+}
+
+/// Render the Apple style report as Markdown.
+///
+/// The report goes in a fenced code block to keep its column layout. Code blocks can't hold links, so the
+/// symbolicated locations that link to source hosting are listed below it.
+pub fn markdown_report(report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<String, Error> {
+    let backtraces = Backtraces::new(report, symbolicate, options)?;
+    let text = render_report(report, symbolicate, options, &backtraces, Markup::Plain)?;
+    let fence = code_fence(&text);
+    let mut markdown = String::new();
+    writeln!(markdown, "{}", fence).unwrap();
+    markdown.push_str(&text);
+    if !text.ends_with('\n') {
+        markdown.push('\n');
+    }
+    writeln!(markdown, "{}", fence).unwrap();
+
+    let mut links = Vec::new();
+    links.extend(frame_links("Last Exception Backtrace", &backtraces.exception));
+    for (thread, frames) in report.get_threads().iter().zip(&backtraces.threads) {
+        links.extend(frame_links(&format!("Thread {}", thread.get_thread_number()), frames));
+    }
+    if !links.is_empty() {
+        writeln!(markdown).unwrap();
+        writeln!(markdown, "Source links:").unwrap();
+        writeln!(markdown).unwrap();
+        for link in &links {
+            writeln!(markdown, "- {}", link).unwrap();
+        }
+    }
+    Ok(markdown)
+}
+
+/// A backtick fence longer than any run of backticks in `text`.
+fn code_fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// List items linking the locations of `frames`, like `Thread 0, frame 3: [MZWriteQueue.m:408](https://...)`.
+fn frame_links(label: &str, frames: &[BacktraceFrame]) -> Vec<String> {
+    let mut ret = Vec::new();
+    for frame in frames {
+        if let (Some(location), Some(url)) = (frame.location.as_ref(), frame.link.as_ref()) {
+            let file = location.file()
+                .and_then(|file| Path::new(file).file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "???".into());
+            let name = format!("{}:{}", file, location.line().unwrap_or(0));
+            ret.push(format!("{}, frame {}: [{}]({})", label, frame.index, escape_markdown(&name), escape_url(url)));
+        }
+    }
+    ret
+}
+
+fn escape_markdown(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\`*_[]<>".contains(c) {
+            ret.push('\\');
+        }
+        ret.push(c);
+    }
+    ret
+}
+
+/// Percent-encode what would end a Markdown link destination.
+fn escape_url(url: &str) -> String {
+    url.replace(' ', "%20").replace('(', "%28").replace(')', "%29")
+}
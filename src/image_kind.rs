@@ -0,0 +1,112 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::backtrace::is_in_app;
+use super::glob::Glob;
+use super::protos::crash_report::CrashReport;
+
+/// Where a binary image comes from.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ImageKind {
+    /// The executable of the crashed process.
+    MainExecutable,
+    /// A framework, library or plugin inside the app bundle.
+    AppFramework,
+    /// Part of the OS, like `/System/Library` or `/usr/lib`.
+    System,
+    /// Part of a simulator runtime.
+    SimulatorRuntime,
+    /// A known third-party SDK.
+    ThirdPartySdk { name: String },
+    /// Anything else, like libraries installed in `/usr/local`.
+    Other,
+}
+
+impl fmt::Display for ImageKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageKind::MainExecutable => write!(f, "app"),
+            ImageKind::AppFramework => write!(f, "app framework"),
+            ImageKind::System => write!(f, "system"),
+            ImageKind::SimulatorRuntime => write!(f, "simulator"),
+            ImageKind::ThirdPartySdk { name } => write!(f, "sdk: {}", name),
+            ImageKind::Other => write!(f, "other"),
+        }
+    }
+}
+
+const SYSTEM_PREFIXES : &[&str] = &["/System/Library/", "/usr/lib/", "/System/iOSSupport/", "/Library/Apple/", "/Developer/"];
+
+const SIMULATOR_MARKERS : &[&str] = &["/CoreSimulator/Profiles/Runtimes/", "Simulator.platform/", "/RuntimeRoot/"];
+
+/// A third-party SDK to recognize by path or bundle identifier.
+#[derive(Clone, Debug)]
+pub struct Sdk {
+    /// Name to report, like `Sparkle`.
+    pub name: String,
+    /// Bundle identifier, like `org.sparkle-project.Sparkle`.
+    ///
+    /// Images don't carry their bundle identifier, so it matches the binary of the framework named after its
+    /// last component, like `Sparkle.framework/Sparkle` or `Sparkle.framework/Versions/A/Sparkle`.
+    pub bundle_id: Option<String>,
+    /// Image paths of the SDK, like `*/Sparkle.framework/*`.
+    pub paths: Vec<Glob>,
+}
+
+impl Sdk {
+    fn matches(&self, image_path: &str) -> bool {
+        if self.paths.iter().any(|glob| glob.is_match(image_path)) {
+            return true;
+        }
+        match self.bundle_id.as_ref().and_then(|bundle_id| bundle_id.rsplit('.').next()) {
+            Some(name) => is_framework_binary(image_path, name),
+            None => false,
+        }
+    }
+}
+
+/// Whether `image_path` is the binary of the framework `name`, in a flat or versioned bundle.
+fn is_framework_binary(image_path: &str, name: &str) -> bool {
+    let framework = format!("{}.framework", name);
+    let components : Vec<&str> = Path::new(image_path).iter().rev().take(4).map(|component| component.to_str().unwrap_or("")).collect();
+    if components.len() < 2 || components[0] != name {
+        return false;
+    }
+    components[1] == framework || (components.len() == 4 && components[2] == "Versions" && components[3] == framework)
+}
+
+/// Classifies images by their path.
+#[derive(Clone, Debug, Default)]
+pub struct ImageClassifier {
+    /// Known third-party SDKs. They are recognized before anything else but the main executable.
+    pub sdks: Vec<Sdk>,
+}
+
+impl ImageClassifier {
+    pub fn new(sdks: Vec<Sdk>) -> ImageClassifier {
+        ImageClassifier { sdks }
+    }
+
+    pub fn classify(&self, report: &CrashReport, image_path: &str) -> ImageKind {
+        if image_path == report.get_process_info().get_process_path() {
+            return ImageKind::MainExecutable;
+        }
+        if let Some(sdk) = self.sdks.iter().find(|sdk| sdk.matches(image_path)) {
+            return ImageKind::ThirdPartySdk { name: sdk.name.clone() };
+        }
+        /* Simulator runtimes have their own /System/Library below the runtime root */
+        if SIMULATOR_MARKERS.iter().any(|marker| image_path.contains(marker)) {
+            return ImageKind::SimulatorRuntime;
+        }
+        if is_in_app(report, image_path) {
+            return ImageKind::AppFramework;
+        }
+        if SYSTEM_PREFIXES.iter().any(|prefix| image_path.starts_with(prefix)) {
+            return ImageKind::System;
+        }
+        ImageKind::Other
+    }
+}
@@ -0,0 +1,345 @@
+use failure::ResultExt;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::arch::Arch;
+use super::backtrace::{image_uuid, symbolicate_frames, symbolicate_threads, BacktraceFrame, Provenance};
+use super::blame::{self, BlameLine, BlameSummary};
+use super::disasm::{self, Disassembly};
+use super::fault::{self, FaultKind};
+use super::hang::{self, HangAnalysis};
+use super::image_kind::{ImageClassifier, ImageKind};
+use super::mach_exception::MachException;
+use super::options::Options;
+use super::protos::crash_report::*;
+use super::recursion::{self, RecursionAnalysis};
+use super::registers::{self, RegisterAnnotation};
+use super::signal::Signal;
+use super::source::SourceContext;
+use super::symbolicate::{Location, Symbolicate};
+use super::triage::Tag;
+use super::text::{code_type, os_name};
+use crate::error::{Error, ErrorKind};
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    incident_identifier: Option<String>,
+    hardware_model: Option<&'a str>,
+    process: Option<JsonProcess<'a>>,
+    identifier: &'a str,
+    version: &'a str,
+    code_type: &'static str,
+    os: JsonOs<'a>,
+    signal: JsonSignal<'a>,
+    fault: Option<JsonFault>,
+    exception: Option<JsonException<'a>>,
+    crashed_thread: Option<u32>,
+    tags: Option<Vec<Tag>>,
+    hang: Option<HangAnalysis>,
+    recursion: Option<RecursionAnalysis>,
+    blame: Option<BlameSummary>,
+    disassembly: Option<Disassembly>,
+    threads: Vec<JsonThread<'a>>,
+    binary_images: Vec<JsonImage<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonProcess<'a> {
+    name: Option<&'a str>,
+    id: u32,
+    path: Option<&'a str>,
+    parent_name: Option<&'a str>,
+    parent_id: u32,
+    native: bool,
+}
+
+#[derive(Serialize)]
+struct JsonOs<'a> {
+    name: &'static str,
+    version: &'a str,
+    build: Option<&'a str>,
+    timestamp: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct JsonSignal<'a> {
+    name: &'a str,
+    number: Option<u32>,
+    code: &'a str,
+    code_name: Option<&'static str>,
+    code_value: Option<i64>,
+    explanation: String,
+    address: u64,
+    mach_exception: Option<JsonMachException>,
+}
+
+#[derive(Serialize)]
+struct JsonMachException {
+    #[serde(rename = "type")]
+    exception_type: u64,
+    name: Option<&'static str>,
+    codes: Vec<u64>,
+    code_name: Option<&'static str>,
+    description: String,
+}
+
+impl JsonMachException {
+    fn new(exception: MachException) -> JsonMachException {
+        JsonMachException {
+            exception_type: exception.exception_type,
+            name: exception.name(),
+            code_name: exception.code_name(),
+            description: exception.codes_description(),
+            codes: exception.codes,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonFault {
+    address: u64,
+    #[serde(flatten)]
+    kind: FaultKind,
+    description: String,
+}
+
+#[derive(Serialize)]
+struct JsonException<'a> {
+    name: &'a str,
+    reason: &'a str,
+    frames: Vec<JsonFrame>,
+}
+
+#[derive(Serialize)]
+struct JsonThread<'a> {
+    number: u32,
+    crashed: bool,
+    frames: Vec<JsonFrame>,
+    registers: Vec<JsonRegister<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonRegister<'a> {
+    name: &'a str,
+    value: u64,
+    annotation: Option<RegisterAnnotation>,
+}
+
+#[derive(Serialize)]
+struct JsonFrame {
+    index: usize,
+    pc: u64,
+    image: Option<String>,
+    image_kind: Option<ImageKind>,
+    image_base: u64,
+    offset: u64,
+    symbol: Option<String>,
+    symbol_offset: Option<u64>,
+    function: Option<String>,
+    function_offset: Option<u64>,
+    location: Option<JsonLocation>,
+    source: Option<SourceContext>,
+    blame: Option<BlameLine>,
+    provenance: Provenance,
+    synthetic: bool,
+}
+
+#[derive(Serialize)]
+struct JsonLocation {
+    file: Option<String>,
+    line: Option<u64>,
+    column: Option<u64>,
+    link: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonImage<'a> {
+    base_address: u64,
+    size: u64,
+    name: &'a str,
+    uuid: Option<String>,
+    image_kind: ImageKind,
+}
+
+impl JsonLocation {
+    fn new(location: &Location, link: Option<String>) -> JsonLocation {
+        JsonLocation {
+            file: location.file().map(|f| f.into()),
+            line: location.line(),
+            column: location.column(),
+            link,
+        }
+    }
+}
+
+impl JsonFrame {
+    fn new(frame: BacktraceFrame) -> JsonFrame {
+        let link = frame.link;
+        JsonFrame {
+            index: frame.index,
+            pc: frame.pc,
+            image: frame.image_path,
+            image_kind: frame.image_kind,
+            image_base: frame.image_base,
+            offset: frame.pc_offset,
+            symbol: frame.symbol,
+            symbol_offset: frame.symbol_offset,
+            function: frame.function,
+            function_offset: frame.function_offset,
+            location: frame.location.as_ref().map(|l| JsonLocation::new(l, link)),
+            source: frame.source,
+            blame: frame.blame,
+            provenance: frame.provenance,
+            synthetic: frame.synthetic,
+        }
+    }
+}
+
+fn json_frames(frames: &[CrashReport_Thread_StackFrame], report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<Vec<JsonFrame>, Error> {
+    Ok(symbolicate_frames(frames, report, symbolicate, options)?
+        .into_iter()
+        .map(JsonFrame::new)
+        .collect())
+}
+
+fn optional(has: bool, value: &str) -> Option<&str> {
+    if has {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+fn json_registers<'a>(report: &CrashReport, thread: &'a CrashReport_Thread, symbolicate: Option<&Symbolicate>) -> Result<Vec<JsonRegister<'a>>, Error> {
+    let mut ret = Vec::new();
+    for reg in thread.get_registers() {
+        ret.push(JsonRegister {
+            name: reg.get_name(),
+            value: reg.get_value(),
+            annotation: registers::annotate_register(report, thread, reg, symbolicate)?,
+        });
+    }
+    Ok(ret)
+}
+
+/// Render a crash report, symbolicated as far as possible, as a JSON document.
+pub fn json_report(report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<String, Error> {
+    let system_info = report.get_system_info();
+    let machine_info = report.get_machine_info();
+    let application_info = report.get_application_info();
+    let signal_info = report.get_signal();
+    let signal = Signal::new(signal_info);
+
+    let process = if report.has_process_info() {
+        let process_info = report.get_process_info();
+        Some(JsonProcess {
+            name: optional(process_info.has_process_name(), process_info.get_process_name()),
+            id: process_info.get_process_id(),
+            path: optional(process_info.has_process_path(), process_info.get_process_path()),
+            parent_name: optional(process_info.has_parent_process_name(), process_info.get_parent_process_name()),
+            parent_id: process_info.get_parent_process_id(),
+            native: process_info.get_native(),
+        })
+    } else {
+        None
+    };
+
+    let exception = if report.has_exception() {
+        let exception = report.get_exception();
+        Some(JsonException {
+            name: exception.get_name(),
+            reason: exception.get_reason(),
+            frames: json_frames(exception.get_frames(), report, symbolicate, options)?,
+        })
+    } else {
+        None
+    };
+
+    /* Symbolicate every thread once, for the analyses below and the backtraces */
+    let thread_frames = symbolicate_threads(report, symbolicate, options)?;
+    let crashed_frames = report.get_threads().iter()
+        .position(|thread| thread.get_crashed())
+        .map(|idx| &thread_frames[idx][..]);
+    let fault = fault::classify(report, symbolicate);
+    let tags = options.triage.map(|triage| triage.evaluate(report, crashed_frames.unwrap_or(&[])));
+    let hang = if hang::is_hang_report(report) {
+        Some(hang::analyze(report, &thread_frames))
+    } else {
+        None
+    };
+    let recursion = recursion::analyze(report, crashed_frames.unwrap_or(&[]), fault.as_ref());
+    let blame_summary = match (options.blame, crashed_frames) {
+        (Some(_), Some(frames)) => Some(blame::summarize(frames, blame::SUSPECT_COMMITS)),
+        _ => None,
+    };
+
+    let mut threads = Vec::new();
+    for (thread, frames) in report.get_threads().iter().zip(thread_frames) {
+        threads.push(JsonThread {
+            number: thread.get_thread_number(),
+            crashed: thread.get_crashed(),
+            frames: frames.into_iter().map(JsonFrame::new).collect(),
+            registers: json_registers(report, thread, symbolicate)?,
+        });
+    }
+
+    let default_classifier = ImageClassifier::default();
+    let classifier = options.image_classifier.unwrap_or(&default_classifier);
+    let json = JsonReport {
+        incident_identifier: Uuid::from_slice(report.get_report_info().get_uuid()).ok().map(|u| u.to_string()),
+        hardware_model: optional(machine_info.has_model(), machine_info.get_model()),
+        process,
+        identifier: application_info.get_identifier(),
+        version: application_info.get_version(),
+        code_type: code_type(report).0,
+        os: JsonOs {
+            name: os_name(report),
+            version: system_info.get_os_version(),
+            build: optional(system_info.has_os_build(), system_info.get_os_build()),
+            timestamp: if system_info.get_timestamp() > 0 { Some(system_info.get_timestamp()) } else { None },
+        },
+        signal: JsonSignal {
+            name: signal_info.get_name(),
+            number: signal.kind.number(),
+            code: signal_info.get_code(),
+            code_name: signal.code_name,
+            code_value: signal.code,
+            explanation: signal.explanation(),
+            address: signal_info.get_address(),
+            mach_exception: if signal_info.has_mach_exception() {
+                Some(JsonMachException::new(MachException::new(signal_info.get_mach_exception(), Arch::of(report))))
+            } else {
+                None
+            },
+        },
+        fault: fault.map(|fault| JsonFault {
+            address: fault.address,
+            description: fault.to_string(),
+            kind: fault.kind,
+        }),
+        exception,
+        crashed_thread: report.get_threads().iter()
+            .find(|thread| thread.get_crashed())
+            .map(|thread| thread.get_thread_number()),
+        tags,
+        hang,
+        recursion,
+        blame: blame_summary,
+        disassembly: match (options.binaries, options.disassemble) {
+            (Some(binaries), true) => disasm::disassemble(report, binaries, symbolicate)?,
+            _ => None,
+        },
+        threads,
+        binary_images: report.get_binary_images().iter()
+            .map(|image| JsonImage {
+                base_address: image.get_base_address(),
+                size: image.get_size(),
+                name: image.get_name(),
+                uuid: image_uuid(image).map(|u| u.to_string()),
+                image_kind: classifier.classify(report, image.get_name()),
+            })
+            .collect(),
+    };
+
+    Ok(serde_json::to_string_pretty(&json).context(ErrorKind::Json)?)
+}
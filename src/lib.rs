@@ -5,15 +5,56 @@ use std::io::{self, Read};
 use protobuf::{Message, ProtobufResult};
 use protobuf::stream::CodedInputStream;
 
+pub mod arch;
+pub mod backtrace;
+pub mod binary;
+pub mod blame;
+pub mod codeowners;
+pub mod corpus;
+pub mod culprit;
+pub mod disasm;
 pub mod error;
+pub mod fault;
+pub mod fingerprint;
+pub mod glob;
+pub mod hang;
+pub mod html;
+pub mod image_kind;
+pub mod json;
+pub mod link;
+pub mod mach_exception;
 #[allow(dead_code, non_upper_case_globals, non_camel_case_types, unused_macros)]
 pub mod machine;
+pub mod objc;
+pub mod options;
 pub mod protos;
+pub mod reason;
+pub mod recursion;
+pub mod regression;
+pub mod registers;
+pub mod signal;
+pub mod source;
+pub mod symbol;
 pub mod symbolicate;
 pub mod text;
+pub mod triage;
+pub mod vm_region;
 
+pub use self::binary::Binaries;
+pub use self::blame::GitBlame;
+pub use self::codeowners::CodeOwners;
+pub use self::corpus::Corpus;
+pub use self::culprit::InAppRules;
+pub use self::fingerprint::{Fingerprint, FingerprintOptions};
+pub use self::html::{html_report, markdown_report};
+pub use self::json::json_report;
+pub use self::link::LinkTemplate;
+pub use self::options::Options;
 pub use self::protos::crash_report::CrashReport;
-pub use self::text::text_report;
+pub use self::source::{PathMap, SourceRoot};
+pub use self::symbol::SymbolName;
+pub use self::text::{text_report, text_report_with_options};
+pub use self::triage::TriageRules;
 pub use symbolicate::{Symbolicate, Location};
 
 pub fn read_report<R: Read>(read: &mut R) -> ProtobufResult<CrashReport> {
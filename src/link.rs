@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use super::blame::CommitPattern;
+use super::options::Options;
+use super::protos::crash_report::CrashReport;
+use super::source::PathMap;
+use super::symbolicate::Location;
+
+/// Builds links to source hosting from a URL template.
+///
+/// The template can use `{repo}`, `{commit}`, `{path}`, `{line}` and `{column}`, as in
+/// `https://git.example/{repo}/blob/{commit}/{path}#L{line}`.
+pub struct LinkTemplate {
+    template: String,
+    repo: String,
+    paths: PathMap,
+    commit_pattern: CommitPattern,
+}
+
+impl LinkTemplate {
+    pub fn new<T: Into<String>, R: Into<String>>(template: T, repo: R) -> LinkTemplate {
+        LinkTemplate {
+            template: template.into(),
+            repo: repo.into(),
+            paths: PathMap::new(),
+            commit_pattern: CommitPattern::default(),
+        }
+    }
+
+    /// Rewrite DWARF paths to paths relative to the repository root.
+    pub fn set_path_map(&mut self, paths: PathMap) {
+        self.paths = paths;
+    }
+
+    pub fn set_commit_pattern(&mut self, pattern: CommitPattern) {
+        self.commit_pattern = pattern;
+    }
+
+    /// Fill in the template.
+    pub fn format(&self, commit: &str, path: &str, line: u64, column: Option<u64>) -> String {
+        self.template
+            .replace("{repo}", &self.repo)
+            .replace("{commit}", commit)
+            .replace("{path}", &escape_path(path))
+            .replace("{line}", &line.to_string())
+            .replace("{column}", &column.unwrap_or(0).to_string())
+    }
+
+    /// Link to a symbolicated location in the commit `report` was built from.
+    ///
+    /// The repository path comes from the path map, then from blame and then from the source root in `options`.
+    pub fn link(&self, report: &CrashReport, location: &Location, options: &Options) -> Option<String> {
+        let file = location.file()?;
+        let line = match location.line() {
+            Some(0) | None => return None,
+            Some(line) => line,
+        };
+        let version = report.get_application_info().get_version();
+        let full_commit = options.blame.and_then(|blame| blame.commit_for_version(version));
+        let commit = match full_commit.as_ref() {
+            Some(commit) => commit.as_str(),
+            None => self.commit_pattern.find(version)?,
+        };
+
+        let path = self.paths.map(Path::new(file))
+            .map(|path| path.to_string_lossy().into_owned())
+            .or_else(|| options.blame.and_then(|blame| full_commit.as_ref().and_then(|c| blame.resolve(c, file))))
+            .or_else(|| {
+                options.source
+                    .and_then(|source| source.relative_path(file))
+                    .map(|path| path.to_string_lossy().into_owned())
+            })?;
+        Some(self.format(commit, &path, line, location.column()))
+    }
+}
+
+/// Percent encode the characters that would end or break the path part of a URL.
+fn escape_path(path: &str) -> String {
+    let mut ret = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            ' ' => ret.push_str("%20"),
+            '#' => ret.push_str("%23"),
+            '%' => ret.push_str("%25"),
+            '?' => ret.push_str("%3F"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
@@ -0,0 +1,321 @@
+use std::fmt;
+
+use super::arch::Arch;
+use super::protos::crash_report::CrashReport_Signal_MachException;
+
+/*
+ * Exception types from <mach/exception_types.h>
+ */
+pub const EXC_BAD_ACCESS      : u64 = 1;  /* Could not access memory */
+pub const EXC_BAD_INSTRUCTION : u64 = 2;  /* Instruction failed */
+pub const EXC_ARITHMETIC      : u64 = 3;  /* Arithmetic exception */
+pub const EXC_EMULATION       : u64 = 4;  /* Emulation instruction */
+pub const EXC_SOFTWARE        : u64 = 5;  /* Software generated exception */
+pub const EXC_BREAKPOINT      : u64 = 6;  /* Trace, breakpoint, etc. */
+pub const EXC_SYSCALL         : u64 = 7;  /* System calls. */
+pub const EXC_MACH_SYSCALL    : u64 = 8;  /* Mach system calls. */
+pub const EXC_RPC_ALERT       : u64 = 9;  /* RPC alert */
+pub const EXC_CRASH           : u64 = 10; /* Abnormal process exit */
+pub const EXC_RESOURCE        : u64 = 11; /* Hit resource consumption limit */
+pub const EXC_GUARD           : u64 = 12; /* Violated guarded resource protections */
+pub const EXC_CORPSE_NOTIFY   : u64 = 13; /* Abnormal process exited to corpse state */
+
+/*
+ * EXC_BAD_ACCESS codes from <mach/kern_return.h>
+ */
+pub const KERN_INVALID_ADDRESS    : u64 = 1;
+pub const KERN_PROTECTION_FAILURE : u64 = 2;
+pub const KERN_MEMORY_ERROR       : u64 = 10;
+pub const KERN_CODESIGN_ERROR     : u64 = 50;
+
+/*
+ * Machine dependent codes from <mach/arm/exception.h> and <mach/i386/exception.h>
+ */
+pub const EXC_ARM_UNDEFINED  : u64 = 1;
+pub const EXC_ARM_FP_IO      : u64 = 1;
+pub const EXC_ARM_FP_DZ      : u64 = 2;
+pub const EXC_ARM_FP_OF      : u64 = 3;
+pub const EXC_ARM_FP_UF      : u64 = 4;
+pub const EXC_ARM_FP_IX      : u64 = 5;
+pub const EXC_ARM_FP_ID      : u64 = 7;
+pub const EXC_ARM_DA_ALIGN   : u64 = 0x101;
+pub const EXC_ARM_DA_DEBUG   : u64 = 0x102;
+pub const EXC_ARM_SP_ALIGN   : u64 = 0x103;
+pub const EXC_ARM_SWP        : u64 = 0x104;
+pub const EXC_ARM_PAC_FAIL   : u64 = 0x105;
+pub const EXC_ARM_BREAKPOINT : u64 = 1;
+
+pub const EXC_I386_INVOP     : u64 = 1;
+pub const EXC_I386_DIV       : u64 = 1;
+pub const EXC_I386_INTO      : u64 = 2;
+pub const EXC_I386_NOEXT     : u64 = 3;
+pub const EXC_I386_EXTOVR    : u64 = 4;
+pub const EXC_I386_EXTERR    : u64 = 5;
+pub const EXC_I386_EMERR     : u64 = 6;
+pub const EXC_I386_BOUND     : u64 = 7;
+pub const EXC_I386_SSEEXTERR : u64 = 8;
+pub const EXC_I386_SGL       : u64 = 1;
+pub const EXC_I386_BPT       : u64 = 2;
+pub const EXC_I386_GPFLT     : u64 = 13;
+
+/// The kind of resource an `EXC_GUARD` protects, from <kern/exc_guard.h>.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GuardType {
+    MachPort,
+    Fd,
+    User,
+    Vnode,
+    VirtualMemory,
+    Unknown(u64),
+}
+
+impl GuardType {
+    pub fn name(self) -> String {
+        match self {
+            GuardType::MachPort => "GUARD_TYPE_MACH_PORT".into(),
+            GuardType::Fd => "GUARD_TYPE_FD".into(),
+            GuardType::User => "GUARD_TYPE_USER".into(),
+            GuardType::Vnode => "GUARD_TYPE_VN".into(),
+            GuardType::VirtualMemory => "GUARD_TYPE_VIRT_MEMORY".into(),
+            GuardType::Unknown(guard_type) => format!("GUARD_TYPE_{}", guard_type),
+        }
+    }
+}
+
+/// The resource limit an `EXC_RESOURCE` was raised for, from <kern/exc_resource.h>.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResourceLimit {
+    /// Used more than `limit` percent of a CPU over `interval` seconds.
+    Cpu { fatal: bool, limit: u64, interval: u64, observed: u64 },
+    /// More than `limit` wakeups per second over `interval` seconds.
+    Wakeups { limit: u64, interval: u64, observed: u64 },
+    /// Footprint went over the high watermark of `limit` MB.
+    Memory { limit: u64 },
+    /// Wrote more than `limit` MB over `interval` seconds.
+    Io { logical: bool, limit: u64, interval: u64, observed: u64 },
+    /// Created more threads than allowed.
+    Threads,
+    Unknown { resource_type: u64, flavor: u64 },
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResourceLimit::Cpu { fatal, limit, interval, observed } => {
+                write!(f, "RESOURCE_TYPE_CPU ({}limit {}% over {} seconds, observed {}%)",
+                    if fatal { "fatal, " } else { "" }, limit, interval, observed)
+            },
+            ResourceLimit::Wakeups { limit, interval, observed } => {
+                write!(f, "RESOURCE_TYPE_WAKEUPS (limit {} per second over {} seconds, observed {} per second)", limit, interval, observed)
+            },
+            ResourceLimit::Memory { limit } => write!(f, "RESOURCE_TYPE_MEMORY (limit {} MB)", limit),
+            ResourceLimit::Io { logical, limit, interval, observed } => {
+                write!(f, "RESOURCE_TYPE_IO ({} writes, limit {} MB over {} seconds, observed {} MB)",
+                    if logical { "logical" } else { "physical" }, limit, interval, observed)
+            },
+            ResourceLimit::Threads => write!(f, "RESOURCE_TYPE_THREADS"),
+            ResourceLimit::Unknown { resource_type, flavor } => write!(f, "RESOURCE_TYPE_{} (flavor {})", resource_type, flavor),
+        }
+    }
+}
+
+/// A Mach exception with its codes interpreted for the architecture it was raised on.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MachException {
+    pub exception_type: u64,
+    pub codes: Vec<u64>,
+    pub arch: Arch,
+}
+
+impl MachException {
+    pub fn new(exception: &CrashReport_Signal_MachException, arch: Arch) -> MachException {
+        MachException {
+            exception_type: exception.get_field_type(),
+            codes: exception.get_codes().to_vec(),
+            arch,
+        }
+    }
+
+    /// Name of the exception type, like `EXC_BAD_ACCESS`.
+    pub fn name(&self) -> Option<&'static str> {
+        let name = match self.exception_type {
+            EXC_BAD_ACCESS => "EXC_BAD_ACCESS",
+            EXC_BAD_INSTRUCTION => "EXC_BAD_INSTRUCTION",
+            EXC_ARITHMETIC => "EXC_ARITHMETIC",
+            EXC_EMULATION => "EXC_EMULATION",
+            EXC_SOFTWARE => "EXC_SOFTWARE",
+            EXC_BREAKPOINT => "EXC_BREAKPOINT",
+            EXC_SYSCALL => "EXC_SYSCALL",
+            EXC_MACH_SYSCALL => "EXC_MACH_SYSCALL",
+            EXC_RPC_ALERT => "EXC_RPC_ALERT",
+            EXC_CRASH => "EXC_CRASH",
+            EXC_RESOURCE => "EXC_RESOURCE",
+            EXC_GUARD => "EXC_GUARD",
+            EXC_CORPSE_NOTIFY => "EXC_CORPSE_NOTIFY",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    fn code(&self, idx: usize) -> Option<u64> {
+        self.codes.get(idx).cloned()
+    }
+
+    /// Name of the first code, like `KERN_INVALID_ADDRESS` or `EXC_ARM_DA_ALIGN`.
+    pub fn code_name(&self) -> Option<&'static str> {
+        let code = self.code(0)?;
+        let name = match (self.exception_type, code) {
+            (EXC_BAD_ACCESS, KERN_INVALID_ADDRESS) => "KERN_INVALID_ADDRESS",
+            (EXC_BAD_ACCESS, KERN_PROTECTION_FAILURE) => "KERN_PROTECTION_FAILURE",
+            (EXC_BAD_ACCESS, KERN_MEMORY_ERROR) => "KERN_MEMORY_ERROR",
+            (EXC_BAD_ACCESS, KERN_CODESIGN_ERROR) => "KERN_CODESIGN_ERROR",
+            (EXC_BAD_ACCESS, EXC_ARM_DA_ALIGN) if self.arch.is_arm() => "EXC_ARM_DA_ALIGN",
+            (EXC_BAD_ACCESS, EXC_ARM_DA_DEBUG) if self.arch.is_arm() => "EXC_ARM_DA_DEBUG",
+            (EXC_BAD_ACCESS, EXC_ARM_SP_ALIGN) if self.arch.is_arm() => "EXC_ARM_SP_ALIGN",
+            (EXC_BAD_ACCESS, EXC_ARM_SWP) if self.arch.is_arm() => "EXC_ARM_SWP",
+            (EXC_BAD_ACCESS, EXC_ARM_PAC_FAIL) if self.arch.is_arm() => "EXC_ARM_PAC_FAIL",
+            (EXC_BAD_ACCESS, EXC_I386_GPFLT) if self.arch.is_x86() => "EXC_I386_GPFLT",
+
+            (EXC_BAD_INSTRUCTION, EXC_ARM_UNDEFINED) if self.arch.is_arm() => "EXC_ARM_UNDEFINED",
+            (EXC_BAD_INSTRUCTION, EXC_I386_INVOP) if self.arch.is_x86() => "EXC_I386_INVOP",
+
+            (EXC_ARITHMETIC, EXC_ARM_FP_IO) if self.arch.is_arm() => "EXC_ARM_FP_IO",
+            (EXC_ARITHMETIC, EXC_ARM_FP_DZ) if self.arch.is_arm() => "EXC_ARM_FP_DZ",
+            (EXC_ARITHMETIC, EXC_ARM_FP_OF) if self.arch.is_arm() => "EXC_ARM_FP_OF",
+            (EXC_ARITHMETIC, EXC_ARM_FP_UF) if self.arch.is_arm() => "EXC_ARM_FP_UF",
+            (EXC_ARITHMETIC, EXC_ARM_FP_IX) if self.arch.is_arm() => "EXC_ARM_FP_IX",
+            (EXC_ARITHMETIC, EXC_ARM_FP_ID) if self.arch.is_arm() => "EXC_ARM_FP_ID",
+            (EXC_ARITHMETIC, EXC_I386_DIV) if self.arch.is_x86() => "EXC_I386_DIV",
+            (EXC_ARITHMETIC, EXC_I386_INTO) if self.arch.is_x86() => "EXC_I386_INTO",
+            (EXC_ARITHMETIC, EXC_I386_NOEXT) if self.arch.is_x86() => "EXC_I386_NOEXT",
+            (EXC_ARITHMETIC, EXC_I386_EXTOVR) if self.arch.is_x86() => "EXC_I386_EXTOVR",
+            (EXC_ARITHMETIC, EXC_I386_EXTERR) if self.arch.is_x86() => "EXC_I386_EXTERR",
+            (EXC_ARITHMETIC, EXC_I386_EMERR) if self.arch.is_x86() => "EXC_I386_EMERR",
+            (EXC_ARITHMETIC, EXC_I386_BOUND) if self.arch.is_x86() => "EXC_I386_BOUND",
+            (EXC_ARITHMETIC, EXC_I386_SSEEXTERR) if self.arch.is_x86() => "EXC_I386_SSEEXTERR",
+
+            (EXC_BREAKPOINT, EXC_ARM_BREAKPOINT) if self.arch.is_arm() => "EXC_ARM_BREAKPOINT",
+            (EXC_BREAKPOINT, EXC_I386_SGL) if self.arch.is_x86() => "EXC_I386_SGL",
+            (EXC_BREAKPOINT, EXC_I386_BPT) if self.arch.is_x86() => "EXC_I386_BPT",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    /// The address that could not be accessed, for `EXC_BAD_ACCESS`.
+    pub fn fault_address(&self) -> Option<u64> {
+        if self.exception_type == EXC_BAD_ACCESS {
+            self.code(1)
+        } else {
+            None
+        }
+    }
+
+    /// The guarded resource type, for `EXC_GUARD`.
+    ///
+    /// The first code packs the guard type in bits 61-63, the flavor in bits 32-60 and the target in bits 0-31.
+    pub fn guard_type(&self) -> Option<GuardType> {
+        if self.exception_type != EXC_GUARD {
+            return None;
+        }
+        let guard_type = match self.code(0)? >> 61 {
+            1 => GuardType::MachPort,
+            2 => GuardType::Fd,
+            3 => GuardType::User,
+            4 => GuardType::Vnode,
+            5 => GuardType::VirtualMemory,
+            guard_type => GuardType::Unknown(guard_type),
+        };
+        Some(guard_type)
+    }
+
+    /// The limit that was hit, for `EXC_RESOURCE`.
+    ///
+    /// The first code packs the resource type in bits 61-63, the flavor in bits 58-60 and the limit below that.
+    /// The second code holds what was observed.
+    pub fn resource_limit(&self) -> Option<ResourceLimit> {
+        if self.exception_type != EXC_RESOURCE {
+            return None;
+        }
+        let code = self.code(0)?;
+        let subcode = self.code(1).unwrap_or(0);
+        let resource_type = code >> 61;
+        let flavor = (code >> 58) & 0x7;
+        let limit = match (resource_type, flavor) {
+            (1, 1) | (1, 2) => ResourceLimit::Cpu {
+                fatal: flavor == 2,
+                limit: code & 0x7f,
+                interval: (code >> 7) & 0x1ff_ffff,
+                observed: subcode & 0x7f,
+            },
+            (2, 1) => ResourceLimit::Wakeups {
+                limit: code & 0xfff,
+                interval: (code >> 20) & 0xf_ffff,
+                observed: subcode & 0xf_ffff,
+            },
+            (3, 1) => ResourceLimit::Memory { limit: code & 0x1fff },
+            (4, 1) | (4, 2) => ResourceLimit::Io {
+                logical: flavor == 2,
+                limit: code & 0x7fff,
+                interval: (code >> 15) & 0x1_ffff,
+                observed: subcode & 0x7fff,
+            },
+            (5, _) => ResourceLimit::Threads,
+            _ => ResourceLimit::Unknown { resource_type, flavor },
+        };
+        Some(limit)
+    }
+
+    /// The exception that was converted into an `EXC_CRASH`, and the signal that terminated the process.
+    ///
+    /// The first code packs the signal in bits 24-31 and the original exception type in bits 20-23.
+    pub fn crash_origin(&self) -> Option<(u64, u64)> {
+        if self.exception_type != EXC_CRASH {
+            return None;
+        }
+        let code = self.code(0)?;
+        Some(((code >> 20) & 0xf, (code >> 24) & 0xff))
+    }
+
+    /// The Exception Type line of Apple's reports, like `EXC_BAD_ACCESS (SIGSEGV)`.
+    pub fn type_description(&self, signal: &str) -> String {
+        let name = self.name()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| format!("{:#x}", self.exception_type));
+        if signal.is_empty() {
+            name
+        } else {
+            format!("{} ({})", name, signal)
+        }
+    }
+
+    /// The Exception Codes line of Apple's reports, like `KERN_INVALID_ADDRESS at 0x0000000000000018`.
+    pub fn codes_description(&self) -> String {
+        if let Some(guard_type) = self.guard_type() {
+            let code = self.code(0).unwrap_or(0);
+            return format!("{} (flavor {:#x}, target {:#x}), id {:#x}",
+                guard_type.name(), (code >> 32) & 0x1fff_ffff, code & 0xffff_ffff, self.code(1).unwrap_or(0));
+        }
+        if let Some(limit) = self.resource_limit() {
+            return limit.to_string();
+        }
+
+        let raw = self.codes.iter()
+            .map(|code| self.format_address(*code))
+            .collect::<Vec<_>>()
+            .join(", ");
+        match (self.code_name(), self.fault_address()) {
+            (Some(name), Some(address)) => format!("{} at {}", name, self.format_address(address)),
+            (Some(name), None) => name.into(),
+            (None, _) => raw,
+        }
+    }
+
+    fn format_address(&self, address: u64) -> String {
+        if self.arch.is_lp64() {
+            format!("{:#018x}", address)
+        } else {
+            format!("{:#010x}", address)
+        }
+    }
+}
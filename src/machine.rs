@@ -7,6 +7,7 @@ type cpu_subtype_t = u64;
  */
 pub const CPU_ARCH_MASK  : cpu_type_t = 0xff000000; /* mask for architecture bits */
 pub const CPU_ARCH_ABI64 : cpu_type_t = 0x01000000; /* 64 bit ABI */
+pub const CPU_ARCH_ABI64_32 : cpu_type_t = 0x02000000; /* ABI for 64-bit hardware with 32-bit types; LP32 */
 
 /*
  *	Machine types known by all.
@@ -27,6 +28,7 @@ pub const CPU_TYPE_MC98000   : cpu_type_t = 10;
 pub const CPU_TYPE_HPPA      : cpu_type_t = 11;
 pub const CPU_TYPE_ARM       : cpu_type_t = 12;
 pub const CPU_TYPE_ARM64     : cpu_type_t = (CPU_TYPE_ARM | CPU_ARCH_ABI64);
+pub const CPU_TYPE_ARM64_32  : cpu_type_t = (CPU_TYPE_ARM | CPU_ARCH_ABI64_32);
 pub const CPU_TYPE_MC88000   : cpu_type_t = 13;
 pub const CPU_TYPE_SPARC     : cpu_type_t = 14;
 pub const CPU_TYPE_I860      : cpu_type_t = 15;
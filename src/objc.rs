@@ -0,0 +1,32 @@
+use super::arch::Arch;
+use super::backtrace::{image_for_address, image_uuid, BacktraceFrame};
+use super::binary::Binaries;
+use super::protos::crash_report::CrashReport;
+use super::registers::{self, RegisterRole};
+
+/// Section holding the selector name strings of an Objective-C image.
+pub const METHNAME_SECTION : &str = "__objc_methname";
+
+/// The selector passed to `objc_msgSend` when the crashed thread died inside it.
+///
+/// Like the `objc_msgSend() selector name:` line of Apple's reports. The selector register has to point
+/// into the `__objc_methname` section of an image in `binaries`, since dSYMs don't carry the strings.
+/// `frames` is the symbolicated crashed thread.
+pub fn msgsend_selector(report: &CrashReport, binaries: &Binaries, frames: &[BacktraceFrame]) -> Option<String> {
+    let thread = report.get_threads().iter().find(|thread| thread.get_crashed())?;
+    let frame = frames.first()?;
+    let name = frame.symbol.as_ref().or(frame.function.as_ref())?;
+    if !name.starts_with("objc_msgSend") {
+        return None;
+    }
+
+    /* The selector is the second argument, after the receiver */
+    let selector = registers::find_by_role(Arch::of(report), thread, RegisterRole::Argument(1))?;
+    let image = image_for_address(report, selector)?;
+    let binary = image_uuid(image).and_then(|uuid| binaries.get(&uuid))?;
+    let offset = selector - image.get_base_address();
+    match binary.find_section(offset) {
+        Some(section) if section.section.iter().any(|name| name == METHNAME_SECTION) => binary.read_c_string(offset),
+        _ => None,
+    }
+}
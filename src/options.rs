@@ -0,0 +1,56 @@
+use super::binary::Binaries;
+use super::blame::GitBlame;
+use super::image_kind::ImageClassifier;
+use super::link::LinkTemplate;
+use super::source::SourceRoot;
+use super::triage::TriageRules;
+
+/// Settings shared by the report renderers.
+pub struct Options<'a> {
+    /// Where to look for source files. Source context is only included when this is set.
+    pub source: Option<&'a SourceRoot>,
+    /// Number of source lines to include before and after a symbolicated line.
+    pub context_lines: u64,
+    /// Repository to blame symbolicated lines in.
+    pub blame: Option<&'a GitBlame>,
+    /// Show the column of symbolicated locations and mark it in source context.
+    pub columns: bool,
+    /// Template for links from symbolicated locations to source hosting.
+    pub links: Option<&'a LinkTemplate>,
+    /// Name frames after their DWARF function instead of the client side symbol, when the dSYM has one.
+    pub prefer_dwarf_names: bool,
+    /// Add the caller of an ARM leaf function from the link register to the crashed thread.
+    pub recover_lr_frame: bool,
+    /// Application binaries, for reading code and constant data that dSYMs don't have.
+    pub binaries: Option<&'a Binaries>,
+    /// Disassemble around the pc of the crashed thread. Needs `binaries`.
+    pub disassemble: bool,
+    /// Recognizes third-party SDKs when classifying the image of each frame.
+    pub image_classifier: Option<&'a ImageClassifier>,
+    /// Tag frames in text reports with the kind of their image.
+    pub image_kinds: bool,
+    /// Rules for tagging reports in text and JSON reports.
+    pub triage: Option<&'a TriageRules>,
+    /// Print repeating frames in text reports as one line per cycle.
+    pub collapse_recursion: bool,
+}
+
+impl<'a> Default for Options<'a> {
+    fn default() -> Options<'a> {
+        Options {
+            source: None,
+            context_lines: 3,
+            blame: None,
+            links: None,
+            columns: false,
+            prefer_dwarf_names: false,
+            recover_lr_frame: false,
+            binaries: None,
+            disassemble: false,
+            image_classifier: None,
+            image_kinds: false,
+            triage: None,
+            collapse_recursion: true,
+        }
+    }
+}
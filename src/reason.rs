@@ -0,0 +1,42 @@
+use regex::Regex;
+
+/// Replaces the parts of exception reasons that differ between occurrences of the same crash.
+///
+/// `*** -[__NSArrayM objectAtIndex:]: index 5 beyond bounds [0 .. 3]` becomes
+/// `*** -[__NSArrayM objectAtIndex:]: index <num> beyond bounds [<num> .. <num>]`.
+#[derive(Clone, Debug)]
+pub struct ReasonNormalizer {
+    /// Patterns and their replacements, applied in order.
+    rules: Vec<(Regex, &'static str)>,
+}
+
+impl Default for ReasonNormalizer {
+    fn default() -> ReasonNormalizer {
+        let rules = vec![
+            (r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b", "<uuid>"),
+            /* Quotes right after a word character are apostrophes, like in can't */
+            (r#"(^|[^\w])"[^"]*""#, r#"${1}"<str>""#),
+            (r"(^|[^\w])'[^']*'", "${1}'<str>'"),
+            ("\u{201c}[^\u{201d}]*\u{201d}", "\u{201c}<str>\u{201d}"),
+            (r"\b0[xX][0-9a-fA-F]+\b", "<addr>"),
+            (r"\b[0-9]+(?:\.[0-9]+)?\b", "<num>"),
+        ];
+        ReasonNormalizer {
+            rules: rules.into_iter().map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), replacement)).collect(),
+        }
+    }
+}
+
+impl ReasonNormalizer {
+    pub fn new() -> ReasonNormalizer {
+        ReasonNormalizer::default()
+    }
+
+    pub fn normalize(&self, reason: &str) -> String {
+        let mut ret = reason.trim().to_string();
+        for (regex, replacement) in &self.rules {
+            ret = regex.replace_all(&ret, *replacement).into_owned();
+        }
+        ret
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use super::backtrace::BacktraceFrame;
+use super::fault::{Fault, FaultKind};
+use super::protos::crash_report::CrashReport;
+
+/// Longest sequence of frames that is looked for as a repeating cycle.
+pub const MAX_CYCLE_LENGTH : usize = 16;
+
+/// A sequence of frames has to follow itself at least this many times to count as recursion.
+pub const MIN_REPEATS : usize = 3;
+
+/// Frames that repeat back to back, like a recursive function or a cycle of mutually recursive ones.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Cycle {
+    /// Index of the first frame of the first repetition.
+    pub start: usize,
+    /// Index of the last frame of the last complete repetition.
+    pub end: usize,
+    /// Names of the frames of one repetition, from the top of the stack.
+    pub frames: Vec<String>,
+    pub repeats: usize,
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "frames {}\u{2013}{} repeat [{}] \u{d7}{}", self.start, self.end, self.frames.join(" \u{2192} "), self.repeats)
+    }
+}
+
+/// Name of a frame for showing in a cycle.
+fn frame_name(frame: &BacktraceFrame) -> String {
+    match frame.symbol.as_ref().or(frame.function.as_ref()) {
+        Some(name) => name.clone(),
+        None => format!("{} + {}", frame.image_name(), frame.pc_offset),
+    }
+}
+
+/// Number of times `frames[start..start + len]` follows itself, including the first time.
+fn repeats(frames: &[BacktraceFrame], start: usize, len: usize) -> usize {
+    let mut count = 1;
+    while start + (count + 1) * len <= frames.len() {
+        let next = start + count * len;
+        let same = (0..len).all(|idx| frames[start + idx].pc == frames[next + idx].pc);
+        if !same {
+            break;
+        }
+        count += 1;
+    }
+    count
+}
+
+/// Find the repeating cycles of `frames`, from the top of the stack.
+///
+/// Frames are compared by pc, since recursive calls return to the same address. Where cycles of
+/// different lengths start at the same frame, the one covering the most frames wins, and of those
+/// the shortest.
+pub fn find_cycles(frames: &[BacktraceFrame]) -> Vec<Cycle> {
+    let mut cycles = Vec::new();
+    let mut start = 0;
+    while start < frames.len() {
+        let mut best : Option<(usize, usize)> = None;
+        for len in 1..=MAX_CYCLE_LENGTH {
+            if start + len * MIN_REPEATS > frames.len() {
+                break;
+            }
+            let count = repeats(frames, start, len);
+            let covered = best.map(|(len, count)| len * count).unwrap_or(0);
+            if count >= MIN_REPEATS && len * count > covered {
+                best = Some((len, count));
+            }
+        }
+        match best {
+            Some((len, count)) => {
+                cycles.push(Cycle {
+                    start: frames[start].index,
+                    end: frames[start + len * count - 1].index,
+                    frames: frames[start..start + len].iter().map(frame_name).collect(),
+                    repeats: count,
+                });
+                start += len * count;
+            },
+            None => start += 1,
+        }
+    }
+    cycles
+}
+
+/// What the stack of the crashed thread suggests.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StackCondition {
+    /// The crashed thread recursed, but didn't fault below its stack pointer.
+    Recursion,
+    /// The crash faulted just below the stack pointer, most likely on the guard page below the stack.
+    ProbableStackOverflow,
+}
+
+impl fmt::Display for StackCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StackCondition::Recursion => write!(f, "recursion"),
+            StackCondition::ProbableStackOverflow => write!(f, "probable stack overflow"),
+        }
+    }
+}
+
+/// Recursion in the crashed thread and whether it likely overflowed the stack.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct RecursionAnalysis {
+    pub condition: StackCondition,
+    pub thread: u32,
+    /// Cycles in the crashed thread. Can be empty for a stack overflow from large stack frames.
+    pub cycles: Vec<Cycle>,
+}
+
+impl fmt::Display for RecursionAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.condition)?;
+        if let Some(cycle) = self.cycles.iter().max_by_key(|cycle| cycle.end - cycle.start) {
+            write!(f, ", thread {} {}", self.thread, cycle)?;
+        }
+        Ok(())
+    }
+}
+
+/// Look for recursion in the symbolicated crashed thread `frames` of `report`, and for a `fault` just below its stack
+/// pointer. Stacks grow down, so a fault above the stack pointer is a bad access to a live frame instead.
+pub fn analyze(report: &CrashReport, frames: &[BacktraceFrame], fault: Option<&Fault>) -> Option<RecursionAnalysis> {
+    let thread = report.get_threads().iter().find(|thread| thread.get_crashed())?;
+    let cycles = find_cycles(frames);
+    let below_stack = match fault.map(|fault| &fault.kind) {
+        Some(FaultKind::Stack { distance, .. }) => *distance < 0,
+        _ => false,
+    };
+    let condition = if below_stack {
+        StackCondition::ProbableStackOverflow
+    } else if !cycles.is_empty() {
+        StackCondition::Recursion
+    } else {
+        return None;
+    };
+    Some(RecursionAnalysis {
+        condition,
+        thread: thread.get_thread_number(),
+        cycles,
+    })
+}
@@ -0,0 +1,481 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::Path;
+
+use failure::ResultExt;
+use serde::Serialize;
+
+use super::arch::Arch;
+use super::backtrace::{image_for_address, image_uuid, strip_symbol_prefix};
+use super::protos::crash_report::*;
+use super::symbolicate::Symbolicate;
+use crate::error::{Error, ErrorKind};
+
+/// Registers that hold code addresses, and are annotated even when they only happen to point into an image.
+const CODE_REGISTERS : &[&str] = &["pc", "lr", "x30", "rip", "eip"];
+
+/// Registers that never hold pointers.
+const NON_POINTER_REGISTERS : &[&str] = &["cpsr", "rflags", "eflags", "cs", "fs", "gs", "ds", "es", "ss"];
+
+/// Where a register value points, when it points into a binary image.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct RegisterAnnotation {
+    pub name: String,
+    pub value: u64,
+    /// Full path of the image the value points into.
+    pub image: String,
+    pub image_base: u64,
+    pub offset: u64,
+    /// Section the value points into, when the image has a dSYM.
+    pub section: Option<String>,
+    /// Function or symbol containing the value.
+    pub symbol: Option<String>,
+    pub symbol_offset: Option<u64>,
+}
+
+impl RegisterAnnotation {
+    /// File name of the image the value points into.
+    pub fn image_name(&self) -> String {
+        Path::new(&self.image).file_name()
+            .unwrap_or(OsStr::new("???"))
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl fmt::Display for RegisterAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} + {:#x}", self.image_name(), self.offset)?;
+        if let Some(section) = self.section.as_ref() {
+            write!(f, " [{}]", section)?;
+        }
+        if let Some(symbol) = self.symbol.as_ref() {
+            write!(f, " ({} + {})", symbol, self.symbol_offset.unwrap_or(0))?;
+        }
+        Ok(())
+    }
+}
+
+/// Annotate a register of `thread` if its value points into a binary image of `report`.
+///
+/// Code registers are always annotated. Other registers are only annotated when the value points into code,
+/// as far as we can tell: when the image has a dSYM that means the `__text` section, otherwise anywhere
+/// in the image, as the image size in the report is the size of its `__TEXT` segment.
+pub fn annotate_register(report: &CrashReport, thread: &CrashReport_Thread, register: &CrashReport_Thread_RegisterValue, symbolicate: Option<&Symbolicate>) -> Result<Option<RegisterAnnotation>, Error> {
+    let name = register.get_name();
+    let value = register.get_value();
+    if NON_POINTER_REGISTERS.contains(&name) {
+        return Ok(None);
+    }
+    let image = match image_for_address(report, value) {
+        Some(image) => image,
+        None => return Ok(None),
+    };
+    let mut ret = RegisterAnnotation {
+        name: name.into(),
+        value,
+        image: image.get_name().into(),
+        image_base: image.get_base_address(),
+        offset: value - image.get_base_address(),
+        section: None,
+        symbol: None,
+        symbol_offset: None,
+    };
+
+    let lookup = image_uuid(image).and_then(|uuid| symbolicate.and_then(|s| s.get(&uuid)));
+    if let Some(lookup) = lookup {
+        ret.section = lookup.find_section(ret.offset).and_then(|region| region.section.clone());
+        if !CODE_REGISTERS.contains(&name) && ret.section.as_ref().map(|s| s != "__text").unwrap_or(false) {
+            return Ok(None);
+        }
+
+        if let Some((start, _)) = lookup.function_range(ret.offset) {
+            let dsym_frame = lookup
+                .find_frames(ret.offset)?
+                .next()
+                .transpose()
+                .context(ErrorKind::Probe(lookup.name.clone(), ret.offset))?;
+            ret.symbol = dsym_frame.and_then(|frame| frame.function_name());
+            ret.symbol_offset = Some(ret.offset - start);
+        }
+        if ret.symbol.is_none() {
+            if let Some((symbol, start)) = lookup.find_symbol(ret.offset) {
+                ret.symbol = Some(strip_symbol_prefix(symbol, report).into());
+                ret.symbol_offset = Some(ret.offset - start);
+            }
+        }
+    }
+
+    /* Fall back on the client side symbol of a frame at the same address */
+    if ret.symbol.is_none() {
+        let frame = thread.get_frames().iter().find(|frame| frame.get_pc() == value && frame.has_symbol());
+        if let Some(frame) = frame {
+            ret.symbol = Some(strip_symbol_prefix(frame.get_symbol().get_name(), report).into());
+            ret.symbol_offset = Some(value - frame.get_symbol().get_start_address());
+        }
+    }
+
+    Ok(Some(ret))
+}
+
+/// Annotate every register of `thread` that points into a binary image.
+pub fn annotate_registers(report: &CrashReport, thread: &CrashReport_Thread, symbolicate: Option<&Symbolicate>) -> Result<Vec<RegisterAnnotation>, Error> {
+    let mut ret = Vec::new();
+    for register in thread.get_registers() {
+        if let Some(annotation) = annotate_register(report, thread, register, symbolicate)? {
+            ret.push(annotation);
+        }
+    }
+    Ok(ret)
+}
+
+/// Find a register by any of its names.
+pub(crate) fn find(registers: &[CrashReport_Thread_RegisterValue], names: &[&str]) -> Option<u64> {
+    registers.iter()
+        .find(|reg| names.contains(&reg.get_name()))
+        .map(|reg| reg.get_value())
+}
+
+/// Decode the set bits of a flags register, from a table of bit positions and names.
+fn decode_flags(value: u64, bits: &[(u32, &'static str)]) -> Vec<&'static str> {
+    bits.iter()
+        .filter(|&&(bit, _)| value & (1 << bit) != 0)
+        .map(|&(_, name)| name)
+        .collect()
+}
+
+const RFLAGS_BITS : &[(u32, &str)] = &[
+    (0, "CF"), (2, "PF"), (4, "AF"), (6, "ZF"), (7, "SF"), (8, "TF"), (9, "IF"), (10, "DF"), (11, "OF"),
+];
+
+const ARM_CPSR_BITS : &[(u32, &str)] = &[
+    (31, "N"), (30, "Z"), (29, "C"), (28, "V"), (27, "Q"), (24, "J"), (9, "E"), (8, "A"), (7, "I"), (6, "F"), (5, "T"),
+];
+
+const ARM64_CPSR_BITS : &[(u32, &str)] = &[
+    (31, "N"), (30, "Z"), (29, "C"), (28, "V"), (21, "SS"), (20, "IL"), (9, "D"), (8, "A"), (7, "I"), (6, "F"),
+];
+
+/// i386 thread state.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct X86ThreadState {
+    pub eax: u64, pub ebx: u64, pub ecx: u64, pub edx: u64,
+    pub edi: u64, pub esi: u64, pub ebp: u64, pub esp: u64,
+    pub ss: u64, pub eflags: u64, pub eip: u64, pub cs: u64,
+    pub ds: u64, pub es: u64, pub fs: u64, pub gs: u64,
+}
+
+impl X86ThreadState {
+    fn new(registers: &[CrashReport_Thread_RegisterValue]) -> Option<X86ThreadState> {
+        Some(X86ThreadState {
+            eax: find(registers, &["eax"])?,
+            ebx: find(registers, &["ebx"])?,
+            ecx: find(registers, &["ecx"])?,
+            edx: find(registers, &["edx"])?,
+            edi: find(registers, &["edi"])?,
+            esi: find(registers, &["esi"])?,
+            ebp: find(registers, &["ebp"])?,
+            esp: find(registers, &["esp"])?,
+            ss: find(registers, &["ss"]).unwrap_or(0),
+            eflags: find(registers, &["eflags", "efl"]).unwrap_or(0),
+            eip: find(registers, &["eip"])?,
+            cs: find(registers, &["cs"]).unwrap_or(0),
+            ds: find(registers, &["ds"]).unwrap_or(0),
+            es: find(registers, &["es"]).unwrap_or(0),
+            fs: find(registers, &["fs"]).unwrap_or(0),
+            gs: find(registers, &["gs"]).unwrap_or(0),
+        })
+    }
+
+    pub fn registers(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("eax", self.eax), ("ebx", self.ebx), ("ecx", self.ecx), ("edx", self.edx),
+            ("edi", self.edi), ("esi", self.esi), ("ebp", self.ebp), ("esp", self.esp),
+            ("ss", self.ss), ("efl", self.eflags), ("eip", self.eip), ("cs", self.cs),
+            ("ds", self.ds), ("es", self.es), ("fs", self.fs), ("gs", self.gs),
+        ]
+    }
+}
+
+/// x86_64 thread state.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct X86_64ThreadState {
+    pub rax: u64, pub rbx: u64, pub rcx: u64, pub rdx: u64,
+    pub rdi: u64, pub rsi: u64, pub rbp: u64, pub rsp: u64,
+    pub r8: u64, pub r9: u64, pub r10: u64, pub r11: u64,
+    pub r12: u64, pub r13: u64, pub r14: u64, pub r15: u64,
+    pub rip: u64, pub rflags: u64, pub cs: u64, pub fs: u64, pub gs: u64,
+}
+
+impl X86_64ThreadState {
+    fn new(registers: &[CrashReport_Thread_RegisterValue]) -> Option<X86_64ThreadState> {
+        Some(X86_64ThreadState {
+            rax: find(registers, &["rax"])?,
+            rbx: find(registers, &["rbx"])?,
+            rcx: find(registers, &["rcx"])?,
+            rdx: find(registers, &["rdx"])?,
+            rdi: find(registers, &["rdi"])?,
+            rsi: find(registers, &["rsi"])?,
+            rbp: find(registers, &["rbp"])?,
+            rsp: find(registers, &["rsp"])?,
+            r8: find(registers, &["r8"])?,
+            r9: find(registers, &["r9"])?,
+            r10: find(registers, &["r10"])?,
+            r11: find(registers, &["r11"])?,
+            r12: find(registers, &["r12"])?,
+            r13: find(registers, &["r13"])?,
+            r14: find(registers, &["r14"])?,
+            r15: find(registers, &["r15"])?,
+            rip: find(registers, &["rip"])?,
+            rflags: find(registers, &["rflags", "rfl"]).unwrap_or(0),
+            cs: find(registers, &["cs"]).unwrap_or(0),
+            fs: find(registers, &["fs"]).unwrap_or(0),
+            gs: find(registers, &["gs"]).unwrap_or(0),
+        })
+    }
+
+    pub fn registers(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("rax", self.rax), ("rbx", self.rbx), ("rcx", self.rcx), ("rdx", self.rdx),
+            ("rdi", self.rdi), ("rsi", self.rsi), ("rbp", self.rbp), ("rsp", self.rsp),
+            ("r8", self.r8), ("r9", self.r9), ("r10", self.r10), ("r11", self.r11),
+            ("r12", self.r12), ("r13", self.r13), ("r14", self.r14), ("r15", self.r15),
+            ("rip", self.rip), ("rfl", self.rflags), ("cs", self.cs), ("fs", self.fs),
+            ("gs", self.gs),
+        ]
+    }
+}
+
+/// ARMv7 thread state.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ArmThreadState {
+    /// r0 to r12. Apple calls r12 `ip`.
+    pub r: [u64; 13],
+    pub sp: u64,
+    pub lr: u64,
+    pub pc: u64,
+    pub cpsr: u64,
+}
+
+impl ArmThreadState {
+    fn new(registers: &[CrashReport_Thread_RegisterValue]) -> Option<ArmThreadState> {
+        let mut ret = ArmThreadState {
+            sp: find(registers, &["sp", "r13"])?,
+            lr: find(registers, &["lr", "r14"])?,
+            pc: find(registers, &["pc", "r15"])?,
+            cpsr: find(registers, &["cpsr"]).unwrap_or(0),
+            ..ArmThreadState::default()
+        };
+        for (idx, value) in ret.r.iter_mut().enumerate() {
+            let name = format!("r{}", idx);
+            *value = if idx == 12 {
+                find(registers, &[&name, "ip"])?
+            } else {
+                find(registers, &[&name])?
+            };
+        }
+        Some(ret)
+    }
+
+    pub fn registers(&self) -> Vec<(&'static str, u64)> {
+        const NAMES : [&str; 13] = ["r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "ip"];
+        let mut ret : Vec<(&'static str, u64)> = NAMES.iter().cloned().zip(self.r.iter().cloned()).collect();
+        ret.extend_from_slice(&[("sp", self.sp), ("lr", self.lr), ("pc", self.pc), ("cpsr", self.cpsr)]);
+        ret
+    }
+}
+
+/// ARM64 thread state, also used for arm64_32.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Arm64ThreadState {
+    /// x0 to x28.
+    pub x: [u64; 29],
+    pub fp: u64,
+    pub lr: u64,
+    pub sp: u64,
+    pub pc: u64,
+    pub cpsr: u64,
+}
+
+impl Arm64ThreadState {
+    fn new(registers: &[CrashReport_Thread_RegisterValue]) -> Option<Arm64ThreadState> {
+        let mut ret = Arm64ThreadState {
+            fp: find(registers, &["fp", "x29"])?,
+            lr: find(registers, &["lr", "x30"])?,
+            sp: find(registers, &["sp", "x31"])?,
+            pc: find(registers, &["pc"])?,
+            cpsr: find(registers, &["cpsr"]).unwrap_or(0),
+            ..Arm64ThreadState::default()
+        };
+        for (idx, value) in ret.x.iter_mut().enumerate() {
+            *value = find(registers, &[&format!("x{}", idx)])?;
+        }
+        Some(ret)
+    }
+
+    pub fn registers(&self) -> Vec<(&'static str, u64)> {
+        const NAMES : [&str; 29] = [
+            "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14",
+            "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27", "x28",
+        ];
+        let mut ret : Vec<(&'static str, u64)> = NAMES.iter().cloned().zip(self.x.iter().cloned()).collect();
+        ret.extend_from_slice(&[("fp", self.fp), ("lr", self.lr), ("sp", self.sp), ("pc", self.pc), ("cpsr", self.cpsr)]);
+        ret
+    }
+}
+
+/// The register state of a thread, typed by architecture.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ThreadState {
+    X86(X86ThreadState),
+    X86_64(X86_64ThreadState),
+    Arm(ArmThreadState),
+    Arm64(Arm64ThreadState),
+    Arm64_32(Arm64ThreadState),
+}
+
+impl ThreadState {
+    /// Build the thread state of `thread`, if the architecture is known and all its registers are in the report.
+    pub fn new(arch: Arch, thread: &CrashReport_Thread) -> Option<ThreadState> {
+        let registers = thread.get_registers();
+        match arch {
+            Arch::X86 => X86ThreadState::new(registers).map(ThreadState::X86),
+            Arch::X86_64 => X86_64ThreadState::new(registers).map(ThreadState::X86_64),
+            Arch::Arm => ArmThreadState::new(registers).map(ThreadState::Arm),
+            Arch::Arm64 => Arm64ThreadState::new(registers).map(ThreadState::Arm64),
+            Arch::Arm64_32 => Arm64ThreadState::new(registers).map(ThreadState::Arm64_32),
+            Arch::PowerPC | Arch::Unknown => None,
+        }
+    }
+
+    pub fn pc(&self) -> u64 {
+        match *self {
+            ThreadState::X86(ref state) => state.eip,
+            ThreadState::X86_64(ref state) => state.rip,
+            ThreadState::Arm(ref state) => state.pc,
+            ThreadState::Arm64(ref state) | ThreadState::Arm64_32(ref state) => state.pc,
+        }
+    }
+
+    pub fn sp(&self) -> u64 {
+        match *self {
+            ThreadState::X86(ref state) => state.esp,
+            ThreadState::X86_64(ref state) => state.rsp,
+            ThreadState::Arm(ref state) => state.sp,
+            ThreadState::Arm64(ref state) | ThreadState::Arm64_32(ref state) => state.sp,
+        }
+    }
+
+    /// Frame pointer. ARMv7 uses r7 on Apple platforms.
+    pub fn fp(&self) -> u64 {
+        match *self {
+            ThreadState::X86(ref state) => state.ebp,
+            ThreadState::X86_64(ref state) => state.rbp,
+            ThreadState::Arm(ref state) => state.r[7],
+            ThreadState::Arm64(ref state) | ThreadState::Arm64_32(ref state) => state.fp,
+        }
+    }
+
+    /// Link register, which x86 doesn't have. The Thumb bit is cleared on ARMv7.
+    pub fn lr(&self) -> Option<u64> {
+        match *self {
+            ThreadState::X86(_) | ThreadState::X86_64(_) => None,
+            ThreadState::Arm(ref state) => Some(state.lr & !1),
+            ThreadState::Arm64(ref state) | ThreadState::Arm64_32(ref state) => Some(state.lr),
+        }
+    }
+
+    /// RFLAGS, EFLAGS or CPSR.
+    pub fn flags(&self) -> u64 {
+        match *self {
+            ThreadState::X86(ref state) => state.eflags,
+            ThreadState::X86_64(ref state) => state.rflags,
+            ThreadState::Arm(ref state) => state.cpsr,
+            ThreadState::Arm64(ref state) | ThreadState::Arm64_32(ref state) => state.cpsr,
+        }
+    }
+
+    /// Names of the set bits of `flags()`.
+    pub fn flag_names(&self) -> Vec<&'static str> {
+        match *self {
+            ThreadState::X86(_) | ThreadState::X86_64(_) => decode_flags(self.flags(), RFLAGS_BITS),
+            ThreadState::Arm(_) => decode_flags(self.flags(), ARM_CPSR_BITS),
+            ThreadState::Arm64(_) | ThreadState::Arm64_32(_) => decode_flags(self.flags(), ARM64_CPSR_BITS),
+        }
+    }
+
+    /// Registers with Apple's names, in the order Apple's reports list them.
+    pub fn registers(&self) -> Vec<(&'static str, u64)> {
+        match *self {
+            ThreadState::X86(ref state) => state.registers(),
+            ThreadState::X86_64(ref state) => state.registers(),
+            ThreadState::Arm(ref state) => state.registers(),
+            ThreadState::Arm64(ref state) | ThreadState::Arm64_32(ref state) => state.registers(),
+        }
+    }
+}
+
+/// What a register is used for, independent of the architecture.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RegisterRole {
+    ProgramCounter,
+    StackPointer,
+    FramePointer,
+    LinkRegister,
+    /// Integer argument register of the calling convention, counting from 0.
+    Argument(usize),
+}
+
+/// Names of the register with `role` on `arch`, Apple's name first.
+fn role_names(arch: Arch, role: RegisterRole) -> &'static [&'static str] {
+    const X86_64_ARGUMENTS : [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+    const ARM_ARGUMENTS : [&str; 4] = ["r0", "r1", "r2", "r3"];
+    const ARM64_ARGUMENTS : [&str; 8] = ["x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7"];
+    const PPC_ARGUMENTS : [&str; 8] = ["r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10"];
+    let arguments : &'static [&'static str] = match arch {
+        Arch::X86_64 => &X86_64_ARGUMENTS,
+        Arch::Arm => &ARM_ARGUMENTS,
+        Arch::Arm64 | Arch::Arm64_32 => &ARM64_ARGUMENTS,
+        Arch::PowerPC => &PPC_ARGUMENTS,
+        /* i386 passes arguments on the stack */
+        Arch::X86 | Arch::Unknown => &[],
+    };
+    match (role, arch) {
+        (RegisterRole::Argument(idx), _) => arguments.get(idx..idx + 1).unwrap_or(&[]),
+        (RegisterRole::ProgramCounter, Arch::X86) => &["eip"],
+        (RegisterRole::ProgramCounter, Arch::X86_64) => &["rip"],
+        (RegisterRole::ProgramCounter, Arch::Arm) => &["pc", "r15"],
+        (RegisterRole::ProgramCounter, Arch::Arm64) | (RegisterRole::ProgramCounter, Arch::Arm64_32) => &["pc"],
+        (RegisterRole::ProgramCounter, Arch::PowerPC) => &["srr0"],
+        (RegisterRole::ProgramCounter, Arch::Unknown) => &["pc", "rip", "eip"],
+        (RegisterRole::StackPointer, Arch::X86) => &["esp"],
+        (RegisterRole::StackPointer, Arch::X86_64) => &["rsp"],
+        (RegisterRole::StackPointer, Arch::Arm) => &["sp", "r13"],
+        (RegisterRole::StackPointer, Arch::Arm64) | (RegisterRole::StackPointer, Arch::Arm64_32) => &["sp", "x31"],
+        (RegisterRole::StackPointer, Arch::PowerPC) => &["r1"],
+        (RegisterRole::StackPointer, Arch::Unknown) => &["sp", "rsp", "esp"],
+        (RegisterRole::FramePointer, Arch::X86) => &["ebp"],
+        (RegisterRole::FramePointer, Arch::X86_64) => &["rbp"],
+        (RegisterRole::FramePointer, Arch::Arm) => &["r7"],
+        (RegisterRole::FramePointer, Arch::Arm64) | (RegisterRole::FramePointer, Arch::Arm64_32) => &["fp", "x29"],
+        (RegisterRole::FramePointer, Arch::PowerPC) => &["r1"],
+        (RegisterRole::FramePointer, Arch::Unknown) => &["fp", "rbp", "ebp"],
+        (RegisterRole::LinkRegister, Arch::X86) | (RegisterRole::LinkRegister, Arch::X86_64) => &[],
+        (RegisterRole::LinkRegister, Arch::Arm) => &["lr", "r14"],
+        (RegisterRole::LinkRegister, Arch::Arm64) | (RegisterRole::LinkRegister, Arch::Arm64_32) => &["lr", "x30"],
+        (RegisterRole::LinkRegister, Arch::PowerPC) | (RegisterRole::LinkRegister, Arch::Unknown) => &["lr"],
+    }
+}
+
+/// The register of `thread` with `role`, looked up by name.
+///
+/// Unlike `ThreadState::new` this only needs the one register to be in the report, and guesses from the common
+/// names when the architecture is unknown. The Thumb bit of the ARMv7 link register is cleared.
+pub fn find_by_role(arch: Arch, thread: &CrashReport_Thread, role: RegisterRole) -> Option<u64> {
+    let value = find(thread.get_registers(), role_names(arch, role))?;
+    if arch == Arch::Arm && role == RegisterRole::LinkRegister {
+        Some(value & !1)
+    } else {
+        Some(value)
+    }
+}
@@ -0,0 +1,168 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+
+use failure::ResultExt;
+use serde::Serialize;
+
+use super::corpus::Corpus;
+use super::fingerprint::Fingerprint;
+use crate::error::{Error, ErrorKind};
+
+/// Thresholds for calling a signature a regression.
+#[derive(Clone, Debug)]
+pub struct RegressionOptions {
+    /// The share of reports has to grow by at least this factor.
+    pub min_ratio: f64,
+    /// Two-proportion z-score the growth has to reach, 1.96 is about 95% confidence.
+    pub min_z_score: f64,
+    /// Signatures with fewer reports than this in the newer version are not reported as increased.
+    pub min_count: u64,
+}
+
+impl Default for RegressionOptions {
+    fn default() -> RegressionOptions {
+        RegressionOptions {
+            min_ratio: 1.5,
+            min_z_score: 1.96,
+            min_count: 3,
+        }
+    }
+}
+
+/// How one signature changed between the versions.
+#[derive(Clone, Debug, Serialize)]
+pub struct SignatureChange {
+    pub fingerprint: Fingerprint,
+    pub old_count: u64,
+    pub new_count: u64,
+    /// Share of all reports of the version.
+    pub old_rate: f64,
+    pub new_rate: f64,
+    /// `new_rate / old_rate`, if the signature was seen before.
+    pub ratio: Option<f64>,
+    pub z_score: Option<f64>,
+}
+
+/// Release health diff between an older and a newer version.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReleaseDiff {
+    pub old_reports: u64,
+    pub new_reports: u64,
+    /// Signatures only seen in the newer version, most reports first.
+    pub new: Vec<SignatureChange>,
+    /// Signatures that got significantly more frequent, largest increase first.
+    pub increased: Vec<SignatureChange>,
+    /// Signatures only seen in the older version, most reports first.
+    pub disappeared: Vec<SignatureChange>,
+}
+
+impl ReleaseDiff {
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self).context(ErrorKind::Json)?)
+    }
+}
+
+/// Report counts by fingerprint hash, with the fingerprint.
+struct Counts<'a> {
+    total: u64,
+    signatures: BTreeMap<&'a str, (&'a Fingerprint, u64)>,
+}
+
+impl<'a> Counts<'a> {
+    fn of(corpus: &'a Corpus, version: Option<&str>) -> Counts<'a> {
+        let mut ret = Counts {
+            total: 0,
+            signatures: BTreeMap::new(),
+        };
+        for group in corpus.groups() {
+            let count = match version {
+                Some(version) => group.versions.get(version).cloned().unwrap_or(0),
+                None => group.count,
+            };
+            if count > 0 {
+                ret.total += count;
+                ret.signatures.insert(&group.fingerprint.hash, (&group.fingerprint, count));
+            }
+        }
+        ret
+    }
+
+    fn rate(&self, count: u64) -> f64 {
+        if self.total > 0 {
+            count as f64 / self.total as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+fn z_score(old_count: u64, old_total: u64, new_count: u64, new_total: u64) -> Option<f64> {
+    if old_total == 0 || new_total == 0 {
+        return None;
+    }
+    let (n1, n2) = (old_total as f64, new_total as f64);
+    let pooled = (old_count + new_count) as f64 / (n1 + n2);
+    let se = (pooled * (1.0 - pooled) * (1.0 / n1 + 1.0 / n2)).sqrt();
+    if se > 0.0 {
+        Some((new_count as f64 / n2 - old_count as f64 / n1) / se)
+    } else {
+        None
+    }
+}
+
+fn diff(old: &Counts, new: &Counts, options: &RegressionOptions) -> ReleaseDiff {
+    let change = |fingerprint: &Fingerprint, old_count: u64, new_count: u64| {
+        let (old_rate, new_rate) = (old.rate(old_count), new.rate(new_count));
+        SignatureChange {
+            fingerprint: fingerprint.clone(),
+            old_count,
+            new_count,
+            old_rate,
+            new_rate,
+            ratio: if old_rate > 0.0 { Some(new_rate / old_rate) } else { None },
+            z_score: z_score(old_count, old.total, new_count, new.total),
+        }
+    };
+
+    let mut ret = ReleaseDiff {
+        old_reports: old.total,
+        new_reports: new.total,
+        new: Vec::new(),
+        increased: Vec::new(),
+        disappeared: Vec::new(),
+    };
+    for (hash, &(fingerprint, new_count)) in &new.signatures {
+        match old.signatures.get(hash) {
+            None => ret.new.push(change(fingerprint, 0, new_count)),
+            Some(&(_, old_count)) => {
+                let change = change(fingerprint, old_count, new_count);
+                let significant = new_count >= options.min_count
+                    && change.ratio.unwrap_or(0.0) >= options.min_ratio
+                    && change.z_score.unwrap_or(0.0) >= options.min_z_score;
+                if significant {
+                    ret.increased.push(change);
+                }
+            },
+        }
+    }
+    for (hash, &(fingerprint, old_count)) in &old.signatures {
+        if !new.signatures.contains_key(hash) {
+            ret.disappeared.push(change(fingerprint, old_count, 0));
+        }
+    }
+
+    ret.new.sort_by_key(|change| Reverse(change.new_count));
+    ret.disappeared.sort_by_key(|change| Reverse(change.old_count));
+    ret.increased.sort_by(|a, b| b.ratio.partial_cmp(&a.ratio).unwrap_or(Ordering::Equal));
+    ret
+}
+
+/// Compare the reports of two corpora, like the previous and the current release.
+pub fn compare(old: &Corpus, new: &Corpus, options: &RegressionOptions) -> ReleaseDiff {
+    diff(&Counts::of(old, None), &Counts::of(new, None), options)
+}
+
+/// Compare two `ApplicationInfo.version`s within one corpus.
+pub fn compare_versions(corpus: &Corpus, old_version: &str, new_version: &str, options: &RegressionOptions) -> ReleaseDiff {
+    diff(&Counts::of(corpus, Some(old_version)), &Counts::of(corpus, Some(new_version)), options)
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use super::protos::crash_report::CrashReport_Signal;
+
+/// The signals that terminate a crashed process, with their Darwin numbers.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SignalKind {
+    Sigill,
+    Sigtrap,
+    Sigabrt,
+    Sigfpe,
+    Sigbus,
+    Sigsegv,
+    Other(String),
+}
+
+impl SignalKind {
+    pub fn from_name(name: &str) -> SignalKind {
+        match name {
+            "SIGILL" => SignalKind::Sigill,
+            "SIGTRAP" => SignalKind::Sigtrap,
+            "SIGABRT" | "SIGIOT" => SignalKind::Sigabrt,
+            "SIGFPE" => SignalKind::Sigfpe,
+            "SIGBUS" => SignalKind::Sigbus,
+            "SIGSEGV" => SignalKind::Sigsegv,
+            name => SignalKind::Other(name.into()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match *self {
+            SignalKind::Sigill => "SIGILL",
+            SignalKind::Sigtrap => "SIGTRAP",
+            SignalKind::Sigabrt => "SIGABRT",
+            SignalKind::Sigfpe => "SIGFPE",
+            SignalKind::Sigbus => "SIGBUS",
+            SignalKind::Sigsegv => "SIGSEGV",
+            SignalKind::Other(ref name) => name,
+        }
+    }
+
+    pub fn number(&self) -> Option<u32> {
+        match *self {
+            SignalKind::Sigill => Some(4),
+            SignalKind::Sigtrap => Some(5),
+            SignalKind::Sigabrt => Some(6),
+            SignalKind::Sigfpe => Some(8),
+            SignalKind::Sigbus => Some(10),
+            SignalKind::Sigsegv => Some(11),
+            SignalKind::Other(_) => None,
+        }
+    }
+
+    /// The `si_code` values of this signal from <sys/signal.h>, with what they mean.
+    fn codes(&self) -> &'static [(&'static str, i64, &'static str)] {
+        match *self {
+            SignalKind::Sigill => &[
+                ("ILL_NOOP", 0, "illegal instruction"),
+                ("ILL_ILLOPC", 1, "illegal opcode"),
+                ("ILL_ILLTRP", 2, "illegal trap"),
+                ("ILL_PRVOPC", 3, "privileged opcode"),
+                ("ILL_ILLOPN", 4, "illegal operand"),
+                ("ILL_ILLADR", 5, "illegal addressing mode"),
+                ("ILL_PRVREG", 6, "privileged register"),
+                ("ILL_COPROC", 7, "coprocessor error"),
+                ("ILL_BADSTK", 8, "internal stack error"),
+            ],
+            SignalKind::Sigtrap => &[
+                ("TRAP_BRKPT", 1, "breakpoint, usually a Swift runtime check or __builtin_trap"),
+                ("TRAP_TRACE", 2, "trace trap"),
+            ],
+            SignalKind::Sigfpe => &[
+                ("FPE_NOOP", 0, "arithmetic error"),
+                ("FPE_FLTDIV", 1, "floating point divide by zero"),
+                ("FPE_FLTOVF", 2, "floating point overflow"),
+                ("FPE_FLTUND", 3, "floating point underflow"),
+                ("FPE_FLTRES", 4, "floating point inexact result"),
+                ("FPE_FLTINV", 5, "invalid floating point operation"),
+                ("FPE_FLTSUB", 6, "subscript out of range"),
+                ("FPE_INTDIV", 7, "integer divide by zero"),
+                ("FPE_INTOVF", 8, "integer overflow"),
+            ],
+            SignalKind::Sigbus => &[
+                ("BUS_NOOP", 0, "bus error"),
+                ("BUS_ADRALN", 1, "invalid address alignment"),
+                ("BUS_ADRERR", 2, "nonexistent physical address"),
+                ("BUS_OBJERR", 3, "object specific hardware error"),
+            ],
+            SignalKind::Sigsegv => &[
+                ("SEGV_NOOP", 0, "segmentation fault"),
+                ("SEGV_MAPERR", 1, "address not mapped to an object"),
+                ("SEGV_ACCERR", 2, "invalid permissions for a mapped object"),
+            ],
+            SignalKind::Sigabrt | SignalKind::Other(_) => &[],
+        }
+    }
+
+    fn summary(&self) -> &'static str {
+        match *self {
+            SignalKind::Sigill => "Illegal instruction",
+            SignalKind::Sigtrap => "Trace or breakpoint trap",
+            SignalKind::Sigabrt => "The process aborted itself, usually through abort() after an uncaught exception or a failed assertion",
+            SignalKind::Sigfpe => "Arithmetic error",
+            SignalKind::Sigbus => "Bus error",
+            SignalKind::Sigsegv => "Invalid memory access",
+            SignalKind::Other(_) => "Terminated by a signal",
+        }
+    }
+}
+
+/// Codes that any signal can have, for signals sent by another process or a timer.
+const GENERIC_CODES : &[(&str, i64, &str)] = &[
+    ("SI_USER", 0x10001, "sent by kill()"),
+    ("SI_QUEUE", 0x10002, "sent by sigqueue()"),
+    ("SI_TIMER", 0x10003, "sent by a timer"),
+    ("SI_ASYNCIO", 0x10004, "sent on asynchronous I/O completion"),
+    ("SI_MESGQ", 0x10005, "sent on message queue arrival"),
+];
+
+/// A BSD signal with its `si_code` normalized.
+///
+/// Reporters write `Signal.code` either as a name like `SEGV_MAPERR` or as a number like `#1` or `1`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Signal {
+    pub kind: SignalKind,
+    /// Name of the code, if it is known.
+    pub code_name: Option<&'static str>,
+    /// Numeric value of the code, if it is known.
+    pub code: Option<i64>,
+    /// The code as the reporter wrote it, when it is neither a known name nor a number.
+    pub unknown_code: Option<String>,
+    /// Faulting address.
+    pub address: u64,
+}
+
+impl Signal {
+    pub fn new(signal: &CrashReport_Signal) -> Signal {
+        Signal::parse(signal.get_name(), signal.get_code(), signal.get_address())
+    }
+
+    pub fn parse(name: &str, code: &str, address: u64) -> Signal {
+        let kind = SignalKind::from_name(name);
+        let code = code.trim();
+        let mut table = kind.codes().iter().chain(GENERIC_CODES.iter());
+
+        let numeric = parse_code(code);
+        let entry = match numeric {
+            Some(value) => table.find(|entry| entry.1 == value),
+            None => table.find(|entry| entry.0 == code),
+        };
+        Signal {
+            kind,
+            code_name: entry.map(|entry| entry.0),
+            code: entry.map(|entry| entry.1).or(numeric),
+            unknown_code: if entry.is_none() && numeric.is_none() { Some(code.into()) } else { None },
+            address,
+        }
+    }
+
+    /// The code as a name when it is known, otherwise as `#n` like PLCrashReporter does, or as written.
+    pub fn code_display(&self) -> String {
+        match (self.code_name, self.code) {
+            (Some(name), _) => name.into(),
+            (None, Some(code)) => format!("#{}", code),
+            (None, None) => self.unknown_code.clone().unwrap_or_default(),
+        }
+    }
+
+    /// A short human readable explanation, like `Invalid memory access: address not mapped to an object`.
+    pub fn explanation(&self) -> String {
+        let detail = self.code_name
+            .and_then(|name| {
+                self.kind.codes().iter()
+                    .chain(GENERIC_CODES.iter())
+                    .find(|entry| entry.0 == name)
+            })
+            .map(|entry| entry.2);
+        match detail {
+            Some(detail) => format!("{}: {}", self.kind.summary(), detail),
+            None => self.kind.summary().into(),
+        }
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.kind.name(), self.code_display())
+    }
+}
+
+fn parse_code(code: &str) -> Option<i64> {
+    let code = code.trim_start_matches('#');
+    match code.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => code.parse().ok(),
+    }
+}
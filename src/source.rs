@@ -0,0 +1,191 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use super::symbolicate::Location;
+
+/// Prefix rewrites from the source paths recorded in DWARF on the build machine to other paths.
+///
+/// This works like `-fdebug-prefix-map`. When several rules match, the one with the longest prefix wins.
+#[derive(Clone, Debug, Default)]
+pub struct PathMap {
+    rules: Vec<(PathBuf, PathBuf)>,
+}
+
+impl PathMap {
+    pub fn new() -> PathMap {
+        PathMap::default()
+    }
+
+    pub fn add<F: Into<PathBuf>, T: Into<PathBuf>>(&mut self, from: F, to: T) {
+        self.rules.push((from.into(), to.into()));
+    }
+
+    pub fn map(&self, path: &Path) -> Option<PathBuf> {
+        self.rules.iter()
+            .filter_map(|(from, to)| {
+                path.strip_prefix(from).ok().map(|rest| (from, to.join(rest)))
+            })
+            .max_by_key(|(from, _)| from.components().count())
+            .map(|(_, mapped)| mapped)
+    }
+}
+
+/// The outcome of looking up source for a location.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceStatus {
+    /// The file was found and the lines are included.
+    ///
+    /// DWARF has no checksum of the file, so edits since the build that keep the line in the file go unnoticed.
+    Found,
+    /// No local file matches the DWARF path.
+    Missing,
+    /// The local file is shorter than the line number, so it is not the file that was built.
+    Short,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SourceLine {
+    pub number: u64,
+    pub text: String,
+}
+
+/// Source lines around a symbolicated location.
+#[derive(Clone, Debug, Serialize)]
+pub struct SourceContext {
+    /// The file as recorded in DWARF.
+    pub file: String,
+    /// The local file it resolved to.
+    pub local_path: Option<PathBuf>,
+    /// The line of the location.
+    pub line: u64,
+    /// The column of the location, if DWARF recorded one.
+    pub column: Option<u64>,
+    pub lines: Vec<SourceLine>,
+    pub status: SourceStatus,
+}
+
+/// A local checkout of the sources an app was built from.
+pub struct SourceRoot {
+    root: PathBuf,
+    paths: PathMap,
+    files: RefCell<HashMap<PathBuf, Rc<Vec<String>>>>,
+}
+
+impl SourceRoot {
+    pub fn new<P: Into<PathBuf>>(root: P) -> SourceRoot {
+        SourceRoot::with_path_map(root, PathMap::new())
+    }
+
+    pub fn with_path_map<P: Into<PathBuf>>(root: P, paths: PathMap) -> SourceRoot {
+        SourceRoot {
+            root: root.into(),
+            paths,
+            files: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn path_map(&self) -> &PathMap {
+        &self.paths
+    }
+
+    /// Find the local file for a source path from DWARF.
+    ///
+    /// Path map rules are tried first and relative results are taken relative to the root.
+    /// Without a matching rule the longest trailing part of `file` that exists below the root is used.
+    pub fn resolve(&self, file: &str) -> Option<PathBuf> {
+        let path = Path::new(file);
+        if let Some(mapped) = self.paths.map(path) {
+            let mapped = self.root.join(mapped);
+            return if mapped.is_file() {
+                Some(mapped)
+            } else {
+                None
+            };
+        }
+
+        let components : Vec<_> = path.components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        for start in 0..components.len() {
+            let mut candidate = self.root.clone();
+            for name in &components[start..] {
+                candidate.push(name);
+            }
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    /// The path of a DWARF source file relative to the root.
+    pub fn relative_path(&self, file: &str) -> Option<PathBuf> {
+        self.resolve(file)
+            .and_then(|path| path.strip_prefix(&self.root).ok().map(|rel| rel.to_path_buf()))
+    }
+
+    /// Read `radius` lines before and after `location`.
+    ///
+    /// Returns `None` when the location has no file or line.
+    pub fn context(&self, location: &Location, radius: u64) -> Option<SourceContext> {
+        let file = location.file()?;
+        let line = match location.line() {
+            Some(0) | None => return None,
+            Some(line) => line,
+        };
+        let mut ret = SourceContext {
+            file: file.into(),
+            local_path: self.resolve(file),
+            line,
+            column: location.column().and_then(|c| if c > 0 { Some(c) } else { None }),
+            lines: Vec::new(),
+            status: SourceStatus::Missing,
+        };
+        let contents = match ret.local_path.as_ref().and_then(|path| self.read(path)) {
+            Some(contents) => contents,
+            None => return Some(ret),
+        };
+        if line as usize > contents.len() {
+            ret.status = SourceStatus::Short;
+            return Some(ret);
+        }
+
+        let first = line.saturating_sub(radius).max(1);
+        let last = (line + radius).min(contents.len() as u64);
+        for number in first..=last {
+            ret.lines.push(SourceLine {
+                number,
+                text: contents[(number - 1) as usize].clone(),
+            });
+        }
+        ret.status = SourceStatus::Found;
+        Some(ret)
+    }
+
+    fn read(&self, path: &Path) -> Option<Rc<Vec<String>>> {
+        if let Some(contents) = self.files.borrow().get(path) {
+            return Some(contents.clone());
+        }
+        let bytes = fs::read(path).ok()?;
+        let contents : Vec<String> = String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(|line| line.into())
+            .collect();
+        let contents = Rc::new(contents);
+        self.files.borrow_mut().insert(path.to_path_buf(), contents.clone());
+        Some(contents)
+    }
+}
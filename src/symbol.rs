@@ -0,0 +1,234 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    ObjectiveC,
+    Swift,
+    Cpp,
+    C,
+}
+
+/// Whether an Objective-C method is `-` or `+`, or a Swift method is `static`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MethodKind {
+    Instance,
+    Class,
+}
+
+/// A symbol name taken apart.
+///
+/// Parsing is best effort. Names that still look mangled only get their language.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SymbolName {
+    pub language: Language,
+    /// Swift module.
+    pub module: Option<String>,
+    /// C++ namespaces, outermost first.
+    pub namespaces: Vec<String>,
+    /// Class or type, including Swift nested types like `Outer.Inner`.
+    pub type_name: Option<String>,
+    /// Objective-C category, like `NSEventRouting` in `NSWindow(NSEventRouting)`.
+    pub category: Option<String>,
+    /// Method, selector, accessor or function name.
+    pub method: Option<String>,
+    pub method_kind: Option<MethodKind>,
+}
+
+/// Words Swift puts in front of the demangled name of thunks, closures and specializations.
+const SWIFT_PREFIXES : &[&str] = &[
+    "@objc ",
+    "@nonobjc ",
+    "static ",
+    "merged ",
+    "partial apply for ",
+    "protocol witness for ",
+    "reabstraction thunk helper from ",
+    "thunk for ",
+    "dispatch thunk of ",
+    "generic specialization <",
+    "function signature specialization <",
+];
+
+const SWIFT_ACCESSORS : &[&str] = &["getter", "setter", "modify", "read", "willset", "didset", "unsafeAddressor", "materializeForSet"];
+
+impl SymbolName {
+    fn new(language: Language) -> SymbolName {
+        SymbolName {
+            language,
+            module: None,
+            namespaces: Vec::new(),
+            type_name: None,
+            category: None,
+            method: None,
+            method_kind: None,
+        }
+    }
+
+    /// Parse a client side or DWARF symbol name, with the platform symbol prefix already removed.
+    pub fn parse(name: &str) -> SymbolName {
+        let name = name.trim();
+        if let Some(ret) = parse_objc(name) {
+            return ret;
+        }
+        if is_mangled_swift(name) {
+            return SymbolName::new(Language::Swift);
+        }
+        if name.starts_with("_Z") || name.starts_with("__Z") {
+            return SymbolName::new(Language::Cpp);
+        }
+        if let Some(ret) = parse_swift(name) {
+            return ret;
+        }
+        if let Some(ret) = parse_cpp(name) {
+            return ret;
+        }
+        let mut ret = SymbolName::new(Language::C);
+        ret.method = Some(name.to_string());
+        ret
+    }
+
+    /// The type with its module or namespaces, like `MetaZ.AppDelegate` or `std::vector<int>`.
+    pub fn qualified_type(&self) -> Option<String> {
+        let type_name = self.type_name.as_ref()?;
+        Some(match (self.language, self.module.as_ref()) {
+            (Language::Swift, Some(module)) => format!("{}.{}", module, type_name),
+            (Language::Cpp, _) if !self.namespaces.is_empty() => format!("{}::{}", self.namespaces.join("::"), type_name),
+            _ => type_name.clone(),
+        })
+    }
+}
+
+/// `-[Class(Category) selector:]` and `+[Class selector:]`.
+fn parse_objc(name: &str) -> Option<SymbolName> {
+    let method_kind = if name.starts_with("-[") {
+        MethodKind::Instance
+    } else if name.starts_with("+[") {
+        MethodKind::Class
+    } else {
+        return None;
+    };
+    let end = name.rfind(']')?;
+    let inner = &name[2..end];
+    let space = inner.find(' ')?;
+    let (class, selector) = (&inner[..space], inner[space + 1..].trim());
+
+    let mut ret = SymbolName::new(Language::ObjectiveC);
+    match (class.find('('), class.ends_with(')')) {
+        (Some(open), true) => {
+            ret.type_name = Some(class[..open].to_string());
+            ret.category = Some(class[open + 1..class.len() - 1].to_string());
+        },
+        _ => ret.type_name = Some(class.to_string()),
+    }
+    ret.method = Some(selector.to_string());
+    ret.method_kind = Some(method_kind);
+    Some(ret)
+}
+
+fn is_mangled_swift(name: &str) -> bool {
+    ["$s", "_$s", "$S", "_$S", "_T0", "__T0"].iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// Demangled Swift names like `static MetaZ.Outer.Inner.method(arg:) -> ()` or `MetaZ.Type.property.getter : Swift.Int`.
+fn parse_swift(name: &str) -> Option<SymbolName> {
+    let mut rest = name;
+    let mut is_static = false;
+    let mut is_swift = false;
+    loop {
+        if rest.starts_with("closure #") || rest.starts_with("implicit closure #") {
+            rest = &rest[rest.find(" in ")? + 4..];
+            is_swift = true;
+            continue;
+        }
+        match SWIFT_PREFIXES.iter().find(|prefix| rest.starts_with(*prefix)) {
+            Some(prefix) => {
+                is_static |= *prefix == "static ";
+                is_swift = true;
+                rest = &rest[prefix.len()..];
+                if prefix.ends_with('<') {
+                    rest = &rest[rest.find("> of ")? + 5..];
+                }
+            },
+            None => break,
+        }
+    }
+
+    let path_end = [rest.find('('), rest.find(" : "), rest.find(" -> ")].iter()
+        .filter_map(|idx| *idx)
+        .min()
+        .unwrap_or(rest.len());
+    let path = &rest[..path_end];
+    if path.contains("::") || path.contains(' ') || !path.contains('.') {
+        return None;
+    }
+    /* A bare dotted name with nothing after it is more likely a C symbol like foo.cold.1 */
+    if !is_swift && path_end == rest.len() {
+        return None;
+    }
+
+    let mut parts : Vec<&str> = path.split('.').collect();
+    let mut ret = SymbolName::new(Language::Swift);
+    ret.module = Some(parts.remove(0).to_string());
+    let mut method = parts.pop().map(String::from);
+    if let (Some(accessor), Some(property)) = (method.clone(), parts.last().cloned()) {
+        if SWIFT_ACCESSORS.contains(&accessor.as_str()) {
+            parts.pop();
+            method = Some(format!("{}.{}", property, accessor));
+        }
+    }
+    if !parts.is_empty() {
+        ret.type_name = Some(parts.join("."));
+        ret.method_kind = Some(if is_static { MethodKind::Class } else { MethodKind::Instance });
+    }
+    ret.method = method;
+    Some(ret)
+}
+
+/// Demangled C++ names like `ns::Class<T>::method(int) const`.
+///
+/// Namespaces and classes look the same, so the component before the method is always taken to be the type.
+fn parse_cpp(name: &str) -> Option<SymbolName> {
+    /* Split on :: and stop at the parameter list, ignoring both inside template arguments */
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut end = name.len();
+    let bytes = name.as_bytes();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'<' => depth += 1,
+            b'>' if depth > 0 => depth -= 1,
+            b'(' if depth == 0 && idx > 0 => {
+                end = idx;
+                break;
+            },
+            b':' if depth == 0 && bytes.get(idx + 1) == Some(&b':') => {
+                parts.push(&name[start..idx]);
+                idx += 1;
+                start = idx + 1;
+            },
+            _ => {},
+        }
+        idx += 1;
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    parts.push(&name[start..end]);
+
+    /* Drop a return type in front of the first component */
+    if let Some(space) = parts[0].rfind(' ') {
+        if !parts[0][..space].contains('<') {
+            parts[0] = &parts[0][space + 1..];
+        }
+    }
+
+    let mut ret = SymbolName::new(Language::Cpp);
+    ret.method = parts.pop().map(String::from);
+    ret.type_name = parts.pop().map(String::from);
+    ret.namespaces = parts.into_iter().map(String::from).collect();
+    Some(ret)
+}
@@ -12,6 +12,7 @@ use gimli::{EndianRcSlice, RunTimeEndian};
 use goblin::{peek_bytes, Hint};
 use goblin::mach;
 use goblin::mach::load_command::CommandVariant;
+use goblin::mach::symbols::N_SECT;
 use serde::Deserialize;
 //use lru::LruCache;
 use uuid::Uuid;
@@ -193,6 +194,12 @@ impl<'ctx> Iterator for Frames<'ctx> {
 pub struct Frame(pub addr2line::Frame<EndianRcSlice<RunTimeEndian>>);
 
 impl Frame {
+    pub fn function_name(&self) -> Option<String> {
+        self.0.function.as_ref()
+            .and_then(|func| func.demangle().ok())
+            .map(|name| name.into_owned())
+    }
+
     pub fn location(self) -> Option<Location> {
         self.0.location.map(|l| Location(l))
     }
@@ -210,14 +217,19 @@ impl fmt::Display for Frame {
         if let Some(loc) = self.0.location.as_ref() {
             let unknown = String::from("???");
             if space {
-                write!(f, " ({}:{})",
+                write!(f, " ({}:{}",
                     loc.file.as_ref().unwrap_or(&unknown),
                     loc.line.unwrap_or(0u64))?;
             } else {
-                write!(f, "({}:{})",
+                write!(f, "({}:{}",
                     loc.file.as_ref().unwrap_or(&unknown),
                     loc.line.unwrap_or(0u64))?;
             }
+            /* The alternate form includes the column */
+            match loc.column {
+                Some(column) if f.alternate() && column > 0 => write!(f, ":{})", column)?,
+                _ => write!(f, ")")?,
+            }
         }
         Ok(())
     }
@@ -226,6 +238,20 @@ impl fmt::Display for Frame {
 
 pub struct Location(pub addr2line::Location);
 
+impl Location {
+    pub fn file(&self) -> Option<&str> {
+        self.0.file.as_deref()
+    }
+
+    pub fn line(&self) -> Option<u64> {
+        self.0.line
+    }
+
+    pub fn column(&self) -> Option<u64> {
+        self.0.column
+    }
+}
+
 impl PartialEq for Location {
     fn eq(&self, other: &Location) -> bool {
         self.0.file == other.0.file &&
@@ -241,7 +267,12 @@ impl fmt::Display for Location {
         let unknown = String::from("???");
         write!(f, "{}:{}",
             self.0.file.as_ref().unwrap_or(&unknown),
-            self.0.line.unwrap_or(0u64))
+            self.0.line.unwrap_or(0u64))?;
+        /* The alternate form includes the column */
+        match self.0.column {
+            Some(column) if f.alternate() && column > 0 => write!(f, ":{}", column),
+            _ => Ok(()),
+        }
     }
 }
 
@@ -262,12 +293,111 @@ pub enum LookupError {
     Gimli(#[cause] gimli::Error),
 }
 
+/// A segment, or a section within it, as offsets from the image base.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Region {
+    pub segment: String,
+    pub section: Option<String>,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// The UUID from the `LC_UUID` load command.
+pub(crate) fn mach_uuid<'data>(macho: &mach::MachO<'data>) -> Option<Uuid> {
+    // Return the UUID from the `LC_UUID` load command, if one is present.
+    macho
+        .load_commands
+        .iter()
+        .filter_map(|lc| {
+            match lc.command {
+                CommandVariant::Uuid(ref cmd) => {
+                    //TODO: Uuid should have a `from_array` method that can't fail.
+                    Some(Uuid::from_bytes(cmd.uuid))
+                }
+                _ => None,
+            }
+        }).nth(0)
+}
+
+/// The address the `__TEXT` segment is linked at.
+pub(crate) fn slide<'data>(macho: &mach::MachO<'data>) -> Option<u64> {
+    macho
+        .load_commands
+        .iter()
+        .filter_map(|lc| {
+            match lc.command {
+                CommandVariant::Segment32(ref cmd) => {
+                    //eprintln!("32bit Name {:?}", cmd.name());
+                    if cmd.name().ok() == Some("__TEXT") {
+                        Some(cmd.vmaddr as u64)
+                    } else {
+                        None
+                    }
+                },
+                CommandVariant::Segment64(ref cmd) => {
+                    //eprintln!("64bit Name {:?}", cmd.name());
+                    if cmd.name().ok() == Some("__TEXT") {
+                        Some(cmd.vmaddr)
+                    } else {
+                        None
+                    }
+                },
+                _ => None,
+            }
+        }).nth(0)
+}
+
+/// Segments and sections of the image, as offsets from the image base, sorted by start.
+pub(crate) fn regions<'data>(macho: &mach::MachO<'data>, slide_addr: u64) -> (Vec<Region>, Vec<Region>) {
+    let mut segments = Vec::new();
+    let mut sections = Vec::new();
+    for segment in &macho.segments {
+        /* __PAGEZERO sits below the image and is left out */
+        let segment_name = segment.name().unwrap_or("").to_string();
+        if segment.vmaddr >= slide_addr && segment.vmsize > 0 {
+            segments.push(Region {
+                segment: segment_name.clone(),
+                section: None,
+                start: segment.vmaddr - slide_addr,
+                end: segment.vmaddr + segment.vmsize - slide_addr,
+            });
+        }
+        for section in segment {
+            if let Ok((section, _)) = section {
+                if section.addr >= slide_addr && section.size > 0 {
+                    sections.push(Region {
+                        segment: segment_name.clone(),
+                        section: section.name().ok().map(|name| name.to_string()),
+                        start: section.addr - slide_addr,
+                        end: section.addr + section.size - slide_addr,
+                    });
+                }
+            } else {
+                break;
+            }
+        }
+    }
+    segments.sort_by_key(|region| region.start);
+    sections.sort_by_key(|region| region.start);
+    (segments, sections)
+}
+
 pub struct Lookup {
     pub name: PathBuf,
     pub info: DSYMInfo,
     pub uuid: Option<Uuid>,
     slide_addr: u64,
     addr2line: Context<EndianRcSlice<RunTimeEndian>>,
+    /// Why the DWARF function ranges couldn't be read. Client symbols aren't checked against the dSYM then.
+    pub function_ranges_error: Option<gimli::Error>,
+    /// DWARF function ranges as image offsets, sorted by start.
+    functions: Vec<(u64, u64)>,
+    /// Symbol table entries as image offsets, sorted by address.
+    symbols: Vec<(u64, String)>,
+    /// Segments of the image, sorted by start.
+    segments: Vec<Region>,
+    /// Sections of the image, sorted by start.
+    sections: Vec<Region>,
 }
 
 impl Lookup {
@@ -301,49 +431,6 @@ impl Lookup {
             None
         }
 
-        fn mach_uuid<'data>(macho: &mach::MachO<'data>) -> Option<Uuid> {
-            // Return the UUID from the `LC_UUID` load command, if one is present.
-            macho
-                .load_commands
-                .iter()
-                .filter_map(|lc| {
-                    match lc.command {
-                        CommandVariant::Uuid(ref cmd) => {
-                            //TODO: Uuid should have a `from_array` method that can't fail.
-                            Some(Uuid::from_bytes(cmd.uuid))
-                        }
-                        _ => None,
-                    }
-                }).nth(0)
-        }
-
-        fn slide<'data>(macho: &mach::MachO<'data>) -> Option<u64> {
-            macho
-                .load_commands
-                .iter()
-                .filter_map(|lc| {
-                    match lc.command {
-                        CommandVariant::Segment32(ref cmd) => {
-                            //eprintln!("32bit Name {:?}", cmd.name());
-                            if cmd.name().ok() == Some("__TEXT") {
-                                Some(cmd.vmaddr as u64)
-                            } else {
-                                None
-                            }
-                        },
-                        CommandVariant::Segment64(ref cmd) => {
-                            //eprintln!("64bit Name {:?}", cmd.name());
-                            if cmd.name().ok() == Some("__TEXT") {
-                                Some(cmd.vmaddr)
-                            } else {
-                                None
-                            }
-                        },
-                        _ => None,
-                    }
-                }).nth(0)
-        }
-
 
         let endian = if macho.little_endian {
             gimli::RunTimeEndian::Little
@@ -360,6 +447,51 @@ impl Lookup {
             S::from(gimli::EndianRcSlice::new(Rc::from(&*data), endian))
         }
 
+        fn symbol_table<'data>(macho: &mach::MachO<'data>, slide_addr: u64) -> Vec<(u64, String)> {
+            let mut symbols : Vec<(u64, String)> = macho.symbols()
+                .filter_map(|symbol| symbol.ok())
+                .filter(|(_, nlist)| !nlist.is_stab() && nlist.get_type() == N_SECT && nlist.n_value >= slide_addr)
+                .map(|(name, nlist)| (nlist.n_value - slide_addr, name.to_string()))
+                .collect();
+            symbols.sort_by_key(|symbol| symbol.0);
+            symbols.dedup_by_key(|symbol| symbol.0);
+            symbols
+        }
+
+        fn function_ranges<R: gimli::Reader>(debug_info: &gimli::DebugInfo<R>, debug_abbrev: &gimli::DebugAbbrev<R>, slide_addr: u64) -> Result<Vec<(u64, u64)>, gimli::Error> {
+            let mut ranges = Vec::new();
+            let mut units = debug_info.units();
+            while let Some(unit) = units.next()? {
+                let abbrevs = unit.abbreviations(debug_abbrev)?;
+                let mut entries = unit.entries(&abbrevs);
+                while let Some((_, entry)) = entries.next_dfs()? {
+                    if entry.tag() != gimli::DW_TAG_subprogram {
+                        continue;
+                    }
+                    let low = match entry.attr_value(gimli::DW_AT_low_pc)? {
+                        Some(gimli::AttributeValue::Addr(low)) => low,
+                        _ => continue,
+                    };
+                    /* DWARF 4 and later store the high pc as an offset from the low pc */
+                    let high = match entry.attr(gimli::DW_AT_high_pc)? {
+                        Some(attr) => match attr.value() {
+                            gimli::AttributeValue::Addr(high) => high,
+                            _ => match attr.udata_value() {
+                                Some(size) => low + size,
+                                None => continue,
+                            },
+                        },
+                        None => continue,
+                    };
+                    if low >= slide_addr && high > low {
+                        ranges.push((low - slide_addr, high - slide_addr));
+                    }
+                }
+            }
+            ranges.sort();
+            Ok(ranges)
+        }
+
         let uuid = mach_uuid(macho);
         eprintln!("UUID: {:?}", uuid);
         let debug_abbrev: gimli::DebugAbbrev<_> = load_section(macho, endian);
@@ -370,6 +502,13 @@ impl Lookup {
         let debug_str: gimli::DebugStr<_> = load_section(macho, endian);
 
         let slide_addr = slide(macho).unwrap_or(0u64);
+        /* The ranges only serve to check client symbols, so a dSYM gimli can't fully walk still symbolicates */
+        let (functions, function_ranges_error) = match function_ranges(&debug_info, &debug_abbrev, slide_addr) {
+            Ok(functions) => (functions, None),
+            Err(err) => (Vec::new(), Some(err)),
+        };
+        let symbols = symbol_table(macho, slide_addr);
+        let (segments, sections) = regions(macho, slide_addr);
 
         let ctx = Context::from_sections(
             debug_abbrev,
@@ -385,9 +524,51 @@ impl Lookup {
             uuid: uuid,
             slide_addr: slide_addr,
             addr2line: ctx,
+            function_ranges_error,
+            functions,
+            symbols,
+            segments,
+            sections,
         })
     }
 
+    /// Segments of the image, as offsets from the image base.
+    pub fn segments(&self) -> &[Region] {
+        &self.segments
+    }
+
+    /// The section containing the image offset `probe`.
+    pub fn find_section(&self, probe: u64) -> Option<&Region> {
+        self.sections.iter().find(|region| region.start <= probe && probe < region.end)
+    }
+
+    /// The range of the DWARF function containing the image offset `probe`.
+    pub fn function_range(&self, probe: u64) -> Option<(u64, u64)> {
+        /* Functions don't nest, so the last one starting at or before the probe is the candidate */
+        let idx = match self.functions.binary_search_by_key(&probe, |range| range.0) {
+            Ok(idx) => idx,
+            Err(0) => return None,
+            Err(idx) => idx - 1,
+        };
+        let (start, end) = self.functions[idx];
+        if probe < end {
+            Some((start, end))
+        } else {
+            None
+        }
+    }
+
+    /// The symbol table entry at or before the image offset `probe`, with its start offset.
+    pub fn find_symbol(&self, probe: u64) -> Option<(&str, u64)> {
+        let idx = match self.symbols.binary_search_by_key(&probe, |symbol| symbol.0) {
+            Ok(idx) => idx,
+            Err(0) => return None,
+            Err(idx) => idx - 1,
+        };
+        let (start, ref name) = self.symbols[idx];
+        Some((name.as_str(), start))
+    }
+
     pub fn find_location(&self, probe: u64) -> Result<Option<Location>, Error> {
         Ok(self.addr2line.find_location(self.slide_addr + probe)
             .context(ErrorKind::Probe(self.name.clone(), probe))?
@@ -1,104 +1,129 @@
 use std::cmp;
 use std::ffi::OsStr;
-use std::fmt::Write;
+use std::fmt::{self, Write};
 use std::path::Path;
 
 use chrono::naive::NaiveDateTime;
-use failure::ResultExt;
 use uuid::Uuid;
 
+use super::arch::Arch;
+use super::backtrace::{symbolicate_frames, symbolicate_threads, BacktraceFrame};
+use super::blame::{self, BlameLine, BlameSummary};
+use super::disasm::{self, Disassembly};
+use super::fault;
+use super::hang;
+use super::mach_exception::MachException;
+use super::objc;
+use super::options::Options;
+use super::recursion;
 use super::protos::crash_report::*;
 use super::machine::*;
+use super::registers::{self, ThreadState};
+use super::signal::Signal;
+use super::source::{SourceContext, SourceStatus};
 use super::symbolicate::Symbolicate;
-use crate::error::{Error, ErrorKind};
+use super::vm_region::{self, VmRegion};
+use crate::error::Error;
 
 pub fn text_report(report: &CrashReport, symbolicate: Option<&Symbolicate>) -> Result<String, Error> {
-    let mut text = String::new();
-    let mut lp64 = true;
+    text_report_with_options(report, symbolicate, &Options::default())
+}
 
-    /* Header */
+pub fn text_report_with_options(report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<String, Error> {
+    let backtraces = Backtraces::new(report, symbolicate, options)?;
+    render_report(report, symbolicate, options, &backtraces, Markup::Plain)
+}
 
-    /* Map to apple style OS nane */
-    let os_name = match report.get_system_info().get_operating_system() {
-        CrashReport_SystemInfo_OperatingSystem::MAC_OS_X => "Mac OS X",
-        CrashReport_SystemInfo_OperatingSystem::IPHONE_OS => "iPhone OS",
-        CrashReport_SystemInfo_OperatingSystem::IPHONE_SIMULATOR => "Mac OS X",
-        CrashReport_SystemInfo_OperatingSystem::OS_UNKNOWN => "Unknown",
-    };
+/// The symbolicated backtraces of a report, so they are only symbolicated once per rendering.
+pub(crate) struct Backtraces {
+    /// The last exception backtrace, empty when the report has none.
+    pub exception: Vec<BacktraceFrame>,
+    /// Every thread, in the order of `CrashReport.threads`.
+    pub threads: Vec<Vec<BacktraceFrame>>,
+}
 
-    /* Map to Apple-style code type, and mark whether architecture is LP64 (64-bit) */
-    let mut code_type = "";
-    {
-        /* Attempt to derive the code type from the binary images */
-        for image in report.get_binary_images() {
-            /* Skip images with no specified type */
-            if !image.has_code_type() {
-                continue;
-            }
+impl Backtraces {
+    pub fn new(report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options) -> Result<Backtraces, Error> {
+        let exception = if report.has_exception() {
+            symbolicate_frames(report.get_exception().get_frames(), report, symbolicate, options)?
+        } else {
+            Vec::new()
+        };
+        Ok(Backtraces {
+            exception,
+            threads: symbolicate_threads(report, symbolicate, options)?,
+        })
+    }
+}
 
-            /* Skip unknown encodings */
-            if image.get_code_type().get_encoding() != CrashReport_Processor_TypeEncoding::TYPE_ENCODING_MACH {
-                continue;
-            }
+/// How an Apple style report is marked up.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Markup {
+    Plain,
+    Html,
+}
 
-            match image.get_code_type().get_field_type() {
-                CPU_TYPE_ARM => {
-                    code_type = "ARM";
-                    lp64 = false;
-                },
-                CPU_TYPE_ARM64 => {
-                    code_type = "ARM-64";
-                    lp64 = true;
-                },
-                CPU_TYPE_X86 => {
-                    code_type = "X86";
-                    lp64 = false;
-                },
-                CPU_TYPE_X86_64 => {
-                    code_type = "X86-64";
-                    lp64 = true;
-                },
-                CPU_TYPE_POWERPC => {
-                    code_type = "PPC";
-                    lp64 = false;
-                },
-                // Do nothing, handled below.
-                _ => {}
-            }
+/// Report text being built. Everything written through `fmt::Write` is escaped for the markup.
+pub(crate) struct Output {
+    text: String,
+    markup: Markup,
+}
 
-            /* Stop immediately if code type was discovered */
-            if code_type != "" {
-                break;
-            }
+impl Output {
+    fn new(markup: Markup) -> Output {
+        Output {
+            text: String::new(),
+            markup,
         }
+    }
 
-        /* If we were unable to determine the code type, fall back on the legacy architecture value. */
-        if code_type == "" {
-            match report.get_system_info().get_architecture() {
-                Architecture::ARMV6 | Architecture::ARMV7 => {
-                    code_type = "ARM";
-                    lp64 = false;
-                },
-                Architecture::X86_32 => {
-                    code_type = "X86";
-                    lp64 = false;
-                },
-                Architecture::X86_64 => {
-                    code_type = "X86-64";
-                    lp64 = true;
-                },
-                Architecture::PPC => {
-                    code_type = "PPC";
-                    lp64 = false;
-                },
-                _ => {
-                    code_type = "Unknown";
-                    lp64 = true;
-                },
-            }
+    /// Write `label`, linked to `url` when the markup supports links.
+    fn link(&mut self, label: &str, url: Option<&String>) {
+        match (self.markup, url) {
+            (Markup::Html, Some(url)) => {
+                self.text.push_str("<a href=\"");
+                escape_html(&mut self.text, url);
+                self.text.push_str("\">");
+                escape_html(&mut self.text, label);
+                self.text.push_str("</a>");
+            },
+            _ => self.write_str(label).unwrap(),
         }
+    }
+}
 
+impl fmt::Write for Output {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self.markup {
+            Markup::Plain => self.text.push_str(s),
+            Markup::Html => escape_html(&mut self.text, s),
+        }
+        Ok(())
     }
+}
+
+fn escape_html(text: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => text.push_str("&amp;"),
+            '<' => text.push_str("&lt;"),
+            '>' => text.push_str("&gt;"),
+            '"' => text.push_str("&quot;"),
+            c => text.push(c),
+        }
+    }
+}
+
+pub(crate) fn render_report(report: &CrashReport, symbolicate: Option<&Symbolicate>, options: &Options, backtraces: &Backtraces, markup: Markup) -> Result<String, Error> {
+    let mut text = Output::new(markup);
+
+    /* Header */
+
+    /* Map to apple style OS nane */
+    let os_name = os_name(report);
+
+    /* Map to Apple-style code type, and mark whether architecture is LP64 (64-bit) */
+    let (code_type, lp64) = code_type(report);
 
     {
         let hardware_model = if report.has_machine_info() && report.get_machine_info().has_model() {
@@ -194,8 +219,28 @@ pub fn text_report(report: &CrashReport, symbolicate: Option<&Symbolicate>) -> R
 
     /* Exception code */
     let signal_info = report.get_signal();
-    writeln!(text, "Exception Type:  {}", signal_info.get_name()).unwrap();
-    writeln!(text, "Exception Codes: {} at {:#x}", signal_info.get_code(), signal_info.get_address()).unwrap();
+    if signal_info.has_mach_exception() {
+        let exception = MachException::new(signal_info.get_mach_exception(), Arch::of(report));
+        writeln!(text, "Exception Type:  {}", exception.type_description(signal_info.get_name())).unwrap();
+        writeln!(text, "Exception Codes: {}", exception.codes_description()).unwrap();
+    } else {
+        writeln!(text, "Exception Type:  {}", signal_info.get_name()).unwrap();
+        let signal = Signal::new(signal_info);
+        writeln!(text, "Exception Codes: {} at {:#x}", signal.code_display(), signal.address).unwrap();
+    }
+    let fault = fault::classify(report, symbolicate);
+    if let Some(fault) = fault.as_ref() {
+        writeln!(text, "Fault:           {}", fault).unwrap();
+    }
+
+    let crashed_frames = report.get_threads().iter()
+        .position(|thread| thread.get_crashed())
+        .map(|idx| &backtraces.threads[idx][..])
+        .unwrap_or(&[]);
+
+    if let Some(analysis) = recursion::analyze(report, crashed_frames, fault.as_ref()) {
+        writeln!(text, "Stack:           {}", analysis).unwrap();
+    }
 
     for thread in report.get_threads() {
         if thread.get_crashed() {
@@ -206,47 +251,111 @@ pub fn text_report(report: &CrashReport, symbolicate: Option<&Symbolicate>) -> R
 
     writeln!(text).unwrap();
 
-    /* Uncaught Exception */
-    if report.has_exception() {
+    /* Where the fault address sits between the loaded images */
+    if let Some(fault) = fault.as_ref() {
+        writeln!(text, "VM Regions Near {:#x}:", fault.address).unwrap();
+        let near = vm_region::regions_near(report, symbolicate, options.binaries, fault.address);
+        if let Some(region) = near.before.as_ref() {
+            write_vm_region(&mut text, "   ", region, lp64);
+        }
+        match near.containing.as_ref() {
+            Some(region) => write_vm_region(&mut text, "-->", region, lp64),
+            None => writeln!(text, "--> ").unwrap(),
+        }
+        if let Some(region) = near.after.as_ref() {
+            write_vm_region(&mut text, "   ", region, lp64);
+        }
+        writeln!(text).unwrap();
+    }
+
+    /* Uncaught Exception, and the selector of a crash in objc_msgSend */
+    let selector = match options.binaries {
+        Some(binaries) => objc::msgsend_selector(report, binaries, crashed_frames),
+        None => None,
+    };
+    if report.has_exception() || selector.is_some() {
         writeln!(text, "Application Specific Information:").unwrap();
-        writeln!(text, "*** Terminating app due to uncaught exception '{}', reason: '{}'",
-            report.get_exception().get_name(), report.get_exception().get_reason()
-        ).unwrap();
+        if report.has_exception() {
+            writeln!(text, "*** Terminating app due to uncaught exception '{}', reason: '{}'",
+                report.get_exception().get_name(), report.get_exception().get_reason()
+            ).unwrap();
+        }
+        if let Some(selector) = selector {
+            writeln!(text, "objc_msgSend() selector name: {}", selector).unwrap();
+        }
+
+        writeln!(text).unwrap();
+    }
+
+    /* Tags from triage rules */
+    if let Some(triage) = options.triage {
+        let tags = triage.evaluate(report, crashed_frames);
+        if !tags.is_empty() {
+            writeln!(text, "Triage Tags:").unwrap();
+            for tag in &tags {
+                match tag.note.as_ref() {
+                    Some(note) => writeln!(text, "   {}: {}", tag.tag, note).unwrap(),
+                    None => writeln!(text, "   {}", tag.tag).unwrap(),
+                }
+            }
+            writeln!(text).unwrap();
+        }
+    }
 
+    /* What every thread is blocked on, for hangs */
+    if hang::is_hang_report(report) {
+        let analysis = hang::analyze(report, &backtraces.threads);
+        writeln!(text, "Hang Analysis:").unwrap();
+        writeln!(text, "Main thread: {}", if analysis.main_thread_blocked { "blocked" } else { "not blocked" }).unwrap();
+        for thread in &analysis.threads {
+            write!(text, "   Thread {}: {}", thread.thread, thread.activity).unwrap();
+            if let (Some(symbol), Some(frame)) = (thread.symbol.as_ref(), thread.frame) {
+                write!(text, " in {} (frame {})", symbol, frame).unwrap();
+            }
+            if let Some(object) = thread.wait_object {
+                write!(text, " on {:#x}", object).unwrap();
+            }
+            writeln!(text).unwrap();
+        }
+        if let Some(lock_waits) = analysis.lock_waits.as_ref() {
+            let threads : Vec<String> = lock_waits.threads.iter().map(|thread| thread.to_string()).collect();
+            write!(text, "Threads blocked on locks: {}", threads.join(", ")).unwrap();
+            if !lock_waits.locks.is_empty() {
+                let locks : Vec<String> = lock_waits.locks.iter().map(|lock| format!("{:#x}", lock)).collect();
+                write!(text, " (on {})", locks.join(", ")).unwrap();
+            }
+            writeln!(text).unwrap();
+        }
         writeln!(text).unwrap();
     }
 
+    /* Likely owners and suspect commits, from the crashed thread */
+    if options.blame.is_some() && report.get_threads().iter().any(|thread| thread.get_crashed()) {
+        format_blame_summary(&mut text, &blame::summarize(crashed_frames, blame::SUSPECT_COMMITS));
+    }
+
     /* If an exception stack trace is available, output an Apple-compatible backtrace. */
     if report.has_exception() && report.get_exception().get_frames().len() > 0 {
-        let frames = report.get_exception().get_frames();
-
         /* Create the header. */
         writeln!(text, "Last Exception Backtrace:").unwrap();
 
         /* Write out the frames. In raw reports, Apple writes this out as a simple list of PCs. In the minimally
          * post-processed report, Apple writes this out as full frame entries. We use the latter format. */
-        for idx in 0..frames.len() {
-            let frame = &frames[idx];
-            writeln!(text, "{}", format_stack_frame(&frame, idx, report, lp64, &symbolicate)?).unwrap();
-        }
+        write_stack_frames(&mut text, &backtraces.exception, lp64, options);
         writeln!(text).unwrap();
     }
 
     /* Threads */
     let mut the_crashed_thread = None;
     let mut max_thread_num = 0;
-    for thread in report.get_threads() {
+    for (thread, frames) in report.get_threads().iter().zip(&backtraces.threads) {
         if thread.get_crashed() {
             writeln!(text, "Thread {} Crashed:", thread.get_thread_number()).unwrap();
             the_crashed_thread = Some(thread.clone());
         } else {
             writeln!(text, "Thread {}:", thread.get_thread_number()).unwrap();
         }
-        let frames = thread.get_frames();
-        for idx in 0..frames.len() {
-            let frame = &frames[idx];
-            writeln!(text, "{}", format_stack_frame(&frame, idx, report, lp64, &symbolicate)?).unwrap();
-        }
+        write_stack_frames(&mut text, frames, lp64, options);
         writeln!(text).unwrap();
 
         /* Track the highest thread number */
@@ -259,24 +368,20 @@ pub fn text_report(report: &CrashReport, symbolicate: Option<&Symbolicate>) -> R
             crashed_thread.get_thread_number(), code_type
         ).unwrap();
 
+        /* Use Apple's names and ordering when all registers of the architecture are there */
+        let state = ThreadState::new(Arch::of(report), &crashed_thread);
+        let registers = match state.as_ref() {
+            Some(state) => state.registers(),
+            None => crashed_thread.get_registers().iter().map(|reg| (reg.get_name(), reg.get_value())).collect(),
+        };
+
         let mut reg_column = 0;
-        for reg in crashed_thread.get_registers() {
-            /* Remap register names to match Apple's crash reports */
-            let mut reg_name = reg.get_name();
-            if report.has_machine_info() && report.get_machine_info().get_processor().get_encoding() == CrashReport_Processor_TypeEncoding::TYPE_ENCODING_MACH {
-                let pinfo = report.get_machine_info().get_processor();
-                let arch_type = pinfo.get_field_type() & !CPU_ARCH_MASK;
-
-                /* Apple uses 'ip' rather than 'r12' on ARM */
-                if arch_type == CPU_TYPE_ARM && reg_name == "r12" {
-                    reg_name = "ip";
-                }
-            }
+        for (reg_name, value) in registers {
             /* Use 32-bit or 64-bit fixed width format for the register values */
             if lp64 {
-                write!(text, "{:6}: {:#018x} ", reg_name, reg.get_value()).unwrap();
+                write!(text, "{:6}: {:#018x} ", reg_name, value).unwrap();
             } else {
-                write!(text, "{:6}: {:#010x} ", reg_name, reg.get_value()).unwrap();
+                write!(text, "{:6}: {:#010x} ", reg_name, value).unwrap();
             }
 
             reg_column += 1;
@@ -289,8 +394,28 @@ pub fn text_report(report: &CrashReport, symbolicate: Option<&Symbolicate>) -> R
         if reg_column != 0 {
             writeln!(text).unwrap();
         }
+        if let Some(state) = state.as_ref() {
+            writeln!(text, "{:6}: {}", "flags", state.flag_names().join(" ")).unwrap();
+        }
 
         writeln!(text).unwrap();
+
+        /* The faulting instruction and its neighbours */
+        if let (Some(binaries), true) = (options.binaries, options.disassemble) {
+            if let Some(disassembly) = disasm::disassemble(report, binaries, symbolicate)? {
+                write_disassembly(&mut text, &disassembly, lp64);
+            }
+        }
+
+        /* Registers pointing into code, which find the caller of a leaf function or the function pointer being called */
+        let annotations = registers::annotate_registers(report, &crashed_thread, symbolicate)?;
+        if !annotations.is_empty() {
+            writeln!(text, "Register Annotations:").unwrap();
+            for annotation in annotations {
+                writeln!(text, "{:>6}: {}", annotation.name, annotation).unwrap();
+            }
+            writeln!(text).unwrap();
+        }
     }
 
     /* Images. The iPhone crash report format sorts these in ascending order, by the base address */
@@ -367,106 +492,257 @@ pub fn text_report(report: &CrashReport, symbolicate: Option<&Symbolicate>) -> R
         }
     }
 
-    Ok(text)
+    Ok(text.text)
 }
 
-fn format_stack_frame(frame: &CrashReport_Thread_StackFrame, idx: usize, report: &CrashReport, lp64: bool, symbolicate: &Option<&Symbolicate>) -> Result<String, Error> {
-    /* Base image address containing instrumention pointer, offset of the IP from that base
-     * address, and the associated image name */
-    let mut base_address = 0x0;
-    let mut pc_offset = 0x0;
-    let mut image_name = Path::new("???").display();
-    let mut dsym_frame = None;
-
-    if let Some(image) = image_for_address(report, frame.get_pc()) {
-        /* Fetch the dSYM if it exists */
-        let lookup = if image.has_uuid() {
-            if let Ok(uuid) = Uuid::from_slice(image.get_uuid()) {
-                symbolicate.and_then(|s| s.get(&uuid))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-
-        image_name = Path::new(Path::new(image.get_name()).file_name().unwrap_or(OsStr::new("???"))).display();
-        base_address = image.get_base_address();
-        pc_offset = frame.get_pc() - base_address;
-        if let Some(lookup) = lookup {
-            dsym_frame = lookup
-                .find_frames(pc_offset)?
-                .nth(0)
-                .transpose()
-                .context(ErrorKind::Probe(lookup.name.clone(), pc_offset))?;
-        }
+fn write_stack_frame(text: &mut Output, frame: &BacktraceFrame, lp64: bool, options: &Options) {
+    /* Note that width specifiers are ignored for %@, but work for C strings.
+     * UTF-8 is not correctly handled with %s (it depends on the system encoding), but
+     * UTF-16 is supported via %S, so we use it here */
+    if lp64 {
+        write!(text, "{:<4}{:<35} {:#018x} ", frame.index, frame.image_name(), frame.pc).unwrap();
+    } else {
+        write!(text, "{:<4}{:<35} {:#10x} ", frame.index, frame.image_name(), frame.pc).unwrap();
     }
 
     /* If symbol info is available, the format used in Apple's reports is Sym + OffsetFromSym. Otherwise,
      * the format used is imageBaseAddress + offsetToIP */
-    let symbol_string = if frame.has_symbol() {
-        let mut symbol_name = frame.get_symbol().get_name();
-
-        /* Apple strips the _ symbol prefix in their reports. Only OS X makes use of an
-         * underscore symbol prefix by default. */
-        if symbol_name.starts_with("_") && symbol_name.len() > 1 {
-            match report.get_system_info().get_operating_system() {
-                CrashReport_SystemInfo_OperatingSystem::MAC_OS_X |
-                CrashReport_SystemInfo_OperatingSystem::IPHONE_OS |
-                CrashReport_SystemInfo_OperatingSystem::IPHONE_SIMULATOR => {
-                    let (_, s) = symbol_name.split_at(1);
-                    symbol_name = s;
-                },
-                _ => {
-                    /* Symbol prefix rules are unknown for this OS! */
-                }
-            }
+    if options.prefer_dwarf_names && frame.shows_function(options) {
+        write!(text, "{} + {}", frame.function.as_ref().unwrap(), frame.function_offset.unwrap_or(0)).unwrap();
+    } else if let Some(symbol_name) = frame.symbol.as_ref() {
+        write!(text, "{} + {}", symbol_name, frame.symbol_offset.unwrap_or(0)).unwrap();
+    } else {
+        write!(text, "{:#x} + {}", frame.image_base, frame.pc_offset).unwrap();
+        if let Some(function) = frame.function.as_ref() {
+            write!(text, " {}", function).unwrap();
         }
-
-        let sym_offset = frame.get_pc() - frame.get_symbol().get_start_address();
-        let location = if let Some(loc) = dsym_frame.and_then(|f| f.location() ) {
-            format!(" ({})", loc)
+    }
+    if let Some(loc) = frame.location.as_ref() {
+        write!(text, " (").unwrap();
+        let label = if options.columns {
+            format!("{:#}", loc)
         } else {
-            String::from("")
+            loc.to_string()
         };
-        format!("{} + {}{}", symbol_name, sym_offset, location)
+        text.link(&label, frame.link.as_ref());
+        write!(text, ")").unwrap();
+    }
+    if let (true, Some(kind)) = (options.image_kinds, frame.image_kind.as_ref()) {
+        write!(text, " [{}]", kind).unwrap();
+    }
+    if frame.synthetic {
+        write!(text, " [recovered from lr]").unwrap();
+    }
+    writeln!(text).unwrap();
+}
+
+fn write_disassembly(text: &mut Output, disassembly: &Disassembly, lp64: bool) {
+    let image = Path::new(&disassembly.image).file_name().unwrap_or(OsStr::new("???")).to_string_lossy();
+    if disassembly.starts_at_pc {
+        writeln!(text, "Disassembly ({}, from the crashed pc as the function start is unknown):", image).unwrap();
     } else {
-        let location = if let Some(df) = dsym_frame {
-            format!(" {}", df)
+        writeln!(text, "Disassembly ({}):", image).unwrap();
+    }
+    for instruction in &disassembly.instructions {
+        let marker = if instruction.faulting { "-->" } else { "   " };
+        if lp64 {
+            write!(text, "{} {:#018x}  {:<8} {}", marker, instruction.address, instruction.mnemonic, instruction.operands).unwrap();
         } else {
-            String::from("")
-        };
-        format!("{:#x} + {}{}", base_address, pc_offset, location)
-    };
+            write!(text, "{} {:#010x}  {:<8} {}", marker, instruction.address, instruction.mnemonic, instruction.operands).unwrap();
+        }
+        if !instruction.annotations.is_empty() {
+            write!(text, "    ; {}", instruction.annotations.join(", ")).unwrap();
+        }
+        writeln!(text).unwrap();
+    }
+    writeln!(text).unwrap();
+}
 
-    /* Note that width specifiers are ignored for %@, but work for C strings.
-     * UTF-8 is not correctly handled with %s (it depends on the system encoding), but
-     * UTF-16 is supported via %S, so we use it here */
+fn write_vm_region(text: &mut Output, marker: &str, region: &VmRegion, lp64: bool) {
+    let size = format!("[{:>6}]", vm_region::format_size(region.end - region.start));
     if lp64 {
-        Ok(format!("{:<4}{:<35} {:#018x} {}",
-            idx,
-            image_name,
-            frame.get_pc(),
-            symbol_string))
+        writeln!(text, "{} {:<22} {:016x}-{:016x} {} {}", marker, region.name, region.start, region.end, size, region.image).unwrap();
     } else {
-        Ok(format!("{:<4}{:<35} {:#10x} {}",
-            idx,
-            image_name,
-            frame.get_pc(),
-            symbol_string))
+        writeln!(text, "{} {:<22} {:08x}-{:08x} {} {}", marker, region.name, region.start, region.end, size, region.image).unwrap();
+    }
+}
+
+fn format_source_context(text: &mut Output, context: &SourceContext, columns: bool) {
+    match context.status {
+        SourceStatus::Found => {
+            for line in &context.lines {
+                let marker = if line.number == context.line { "-->" } else { "   " };
+                writeln!(text, "    {} {:>5}  {}", marker, line.number, line.text).unwrap();
+
+                /* Point at the column, keeping tabs so the caret lines up */
+                match context.column {
+                    Some(column) if columns && line.number == context.line => {
+                        let indent : String = line.text.chars()
+                            .take(column as usize - 1)
+                            .map(|c| if c == '\t' { '\t' } else { ' ' })
+                            .collect();
+                        writeln!(text, "               {}^", indent).unwrap();
+                    },
+                    _ => {},
+                }
+            }
+        },
+        SourceStatus::Missing => {
+            writeln!(text, "    (source not found for {})", context.file).unwrap();
+        },
+        SourceStatus::Short => {
+            let local_path = context.local_path.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+            writeln!(text, "    (source {} has no line {}, it is not the file that was built)", local_path, context.line).unwrap();
+        },
     }
 }
 
-fn image_for_address(report: &CrashReport, address: u64) -> Option<&CrashReport_BinaryImage> {
-    for image in report.get_binary_images() {
-        let base_address = image.get_base_address();
-        if base_address <= address && address < (base_address + image.get_size()) {
-            return Some(image);
+fn format_blame_line(blame: &BlameLine) -> String {
+    format!("{:.7} {} {} {}",
+        blame.commit,
+        NaiveDateTime::from_timestamp(blame.author_time, 0).date(),
+        blame.author,
+        blame.summary)
+}
+
+fn format_blame_summary(text: &mut Output, summary: &BlameSummary) {
+    if summary.owners.is_empty() {
+        return;
+    }
+
+    writeln!(text, "Likely Owners:").unwrap();
+    for owner in &summary.owners {
+        let plural = if owner.frames == 1 { "" } else { "s" };
+        writeln!(text, "{} <{}> ({} frame{})", owner.author, owner.author_mail, owner.frames, plural).unwrap();
+    }
+    writeln!(text).unwrap();
+
+    writeln!(text, "Suspect Commits:").unwrap();
+    for suspect in &summary.suspects {
+        writeln!(text, "{}", format_blame_line(suspect)).unwrap();
+    }
+    writeln!(text).unwrap();
+}
+
+fn write_stack_frames(text: &mut Output, frames: &[BacktraceFrame], lp64: bool, options: &Options) {
+    let cycles = if options.collapse_recursion { recursion::find_cycles(frames) } else { Vec::new() };
+    let mut cycles = cycles.iter().peekable();
+    for frame in frames {
+        if let Some(cycle) = cycles.peek() {
+            if frame.index >= cycle.start {
+                if frame.index == cycle.start {
+                    writeln!(text, "... {}", cycle).unwrap();
+                }
+                if frame.index == cycle.end {
+                    cycles.next();
+                }
+                continue;
+            }
+        }
+        write_stack_frame(text, frame, lp64, options);
+        for disagreement in &frame.provenance.disagreements {
+            writeln!(text, "    warning: {}", disagreement).unwrap();
         }
+        if let Some(blame) = frame.blame.as_ref() {
+            writeln!(text, "    {}", format_blame_line(blame)).unwrap();
+        }
+        if let Some(context) = frame.source.as_ref() {
+            format_source_context(text, context, options.columns);
+        }
+    }
+}
+
+/// Map to an Apple style OS name.
+pub(crate) fn os_name(report: &CrashReport) -> &'static str {
+    match report.get_system_info().get_operating_system() {
+        CrashReport_SystemInfo_OperatingSystem::MAC_OS_X => "Mac OS X",
+        CrashReport_SystemInfo_OperatingSystem::IPHONE_OS => "iPhone OS",
+        CrashReport_SystemInfo_OperatingSystem::IPHONE_SIMULATOR => "Mac OS X",
+        CrashReport_SystemInfo_OperatingSystem::OS_UNKNOWN => "Unknown",
+    }
+}
+
+/// Map to an Apple style code type, and whether the architecture is LP64 (64-bit).
+pub(crate) fn code_type(report: &CrashReport) -> (&'static str, bool) {
+    let mut lp64 = true;
+    let mut code_type = "";
+    {
+        /* Attempt to derive the code type from the binary images */
+        for image in report.get_binary_images() {
+            /* Skip images with no specified type */
+            if !image.has_code_type() {
+                continue;
+            }
+
+            /* Skip unknown encodings */
+            if image.get_code_type().get_encoding() != CrashReport_Processor_TypeEncoding::TYPE_ENCODING_MACH {
+                continue;
+            }
+
+            match image.get_code_type().get_field_type() {
+                CPU_TYPE_ARM => {
+                    code_type = "ARM";
+                    lp64 = false;
+                },
+                CPU_TYPE_ARM64 => {
+                    code_type = "ARM-64";
+                    lp64 = true;
+                },
+                CPU_TYPE_ARM64_32 => {
+                    code_type = "ARM64_32";
+                    lp64 = false;
+                },
+                CPU_TYPE_X86 => {
+                    code_type = "X86";
+                    lp64 = false;
+                },
+                CPU_TYPE_X86_64 => {
+                    code_type = "X86-64";
+                    lp64 = true;
+                },
+                CPU_TYPE_POWERPC => {
+                    code_type = "PPC";
+                    lp64 = false;
+                },
+                // Do nothing, handled below.
+                _ => {}
+            }
+
+            /* Stop immediately if code type was discovered */
+            if code_type != "" {
+                break;
+            }
+        }
+
+        /* If we were unable to determine the code type, fall back on the legacy architecture value. */
+        if code_type == "" {
+            match report.get_system_info().get_architecture() {
+                Architecture::ARMV6 | Architecture::ARMV7 => {
+                    code_type = "ARM";
+                    lp64 = false;
+                },
+                Architecture::X86_32 => {
+                    code_type = "X86";
+                    lp64 = false;
+                },
+                Architecture::X86_64 => {
+                    code_type = "X86-64";
+                    lp64 = true;
+                },
+                Architecture::PPC => {
+                    code_type = "PPC";
+                    lp64 = false;
+                },
+                _ => {
+                    code_type = "Unknown";
+                    lp64 = true;
+                },
+            }
+        }
+
     }
 
-    /* Not found */
-    None
+    (code_type, lp64)
 }
 
 
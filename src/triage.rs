@@ -0,0 +1,217 @@
+use std::cmp::Ordering;
+use std::path::Path;
+
+use failure::ResultExt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::backtrace::BacktraceFrame;
+use super::fingerprint::crash_type;
+use super::glob::Glob;
+use super::protos::crash_report::CrashReport;
+use crate::error::{Error, ErrorKind};
+
+/// A rule as written in a rules file. Every condition that is present has to match.
+///
+/// ```json
+/// [{"tag": "rosetta", "note": "Translated x86_64 process", "native": false},
+///  {"tag": "bounds", "exception_name": "^NSRangeException$", "reason": "beyond bounds"}]
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    tag: String,
+    note: Option<String>,
+    /// Regex over `Exception.name`.
+    exception_name: Option<String>,
+    /// Regex over `Exception.reason`.
+    reason: Option<String>,
+    /// Signal, signal code or Mach exception name, like `SIGSEGV`, `SEGV_MAPERR` or `EXC_BAD_ACCESS`.
+    signal: Option<String>,
+    /// Globs over the names of the images in the crashed thread, one has to match.
+    #[serde(default)]
+    images: Vec<String>,
+    /// Globs over the symbol and function names in the crashed thread, one has to match.
+    #[serde(default)]
+    symbols: Vec<String>,
+    /// Lowest matching OS version.
+    os_min: Option<String>,
+    /// First OS version that no longer matches.
+    os_max: Option<String>,
+    /// Globs over `MachineInfo.model`, one has to match.
+    #[serde(default)]
+    models: Vec<String>,
+    /// `ProcessInfo.native`, false for processes translated by Rosetta.
+    native: Option<bool>,
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    tag: String,
+    note: Option<String>,
+    exception_name: Option<Regex>,
+    reason: Option<Regex>,
+    signal: Option<String>,
+    images: Vec<Glob>,
+    symbols: Vec<Glob>,
+    os_min: Option<String>,
+    os_max: Option<String>,
+    models: Vec<Glob>,
+    native: Option<bool>,
+}
+
+fn regex(pattern: Option<String>) -> Result<Option<Regex>, Error> {
+    match pattern {
+        Some(pattern) => Ok(Some(Regex::new(&pattern).context(ErrorKind::Pattern(pattern.clone()))?)),
+        None => Ok(None),
+    }
+}
+
+fn globs(patterns: &[String]) -> Result<Vec<Glob>, Error> {
+    patterns.iter().map(|pattern| Glob::new(pattern)).collect()
+}
+
+/// What rules are evaluated against.
+struct Facts<'a> {
+    report: &'a CrashReport,
+    crash_type: String,
+    /// Names of the images in the crashed thread.
+    images: Vec<String>,
+    /// Symbol and function names in the crashed thread.
+    symbols: Vec<String>,
+}
+
+fn any_match(globs: &[Glob], values: &[String]) -> bool {
+    globs.is_empty() || values.iter().any(|value| globs.iter().any(|glob| glob.is_match(value)))
+}
+
+impl Rule {
+    fn new(spec: RuleSpec) -> Result<Rule, Error> {
+        Ok(Rule {
+            exception_name: regex(spec.exception_name)?,
+            reason: regex(spec.reason)?,
+            images: globs(&spec.images)?,
+            symbols: globs(&spec.symbols)?,
+            models: globs(&spec.models)?,
+            tag: spec.tag,
+            note: spec.note,
+            signal: spec.signal,
+            os_min: spec.os_min,
+            os_max: spec.os_max,
+            native: spec.native,
+        })
+    }
+
+    fn matches(&self, facts: &Facts) -> bool {
+        let report = facts.report;
+        let exception = if report.has_exception() { Some(report.get_exception()) } else { None };
+        if let Some(regex) = self.exception_name.as_ref() {
+            if !exception.iter().any(|exception| regex.is_match(exception.get_name())) {
+                return false;
+            }
+        }
+        if let Some(regex) = self.reason.as_ref() {
+            if !exception.iter().any(|exception| regex.is_match(exception.get_reason())) {
+                return false;
+            }
+        }
+        if let Some(signal) = self.signal.as_ref() {
+            if !facts.crash_type.split('/').any(|part| part == signal) {
+                return false;
+            }
+        }
+        if !any_match(&self.images, &facts.images) || !any_match(&self.symbols, &facts.symbols) {
+            return false;
+        }
+
+        let os_version = report.get_system_info().get_os_version();
+        if let Some(min) = self.os_min.as_ref() {
+            if compare_versions(os_version, min) == Ordering::Less {
+                return false;
+            }
+        }
+        if let Some(max) = self.os_max.as_ref() {
+            if compare_versions(os_version, max) != Ordering::Less {
+                return false;
+            }
+        }
+        let model = if report.has_machine_info() { report.get_machine_info().get_model() } else { "" };
+        if !self.models.is_empty() && !self.models.iter().any(|glob| glob.is_match(model)) {
+            return false;
+        }
+        /* A report that doesn't say whether the process was native matches neither value */
+        match self.native {
+            Some(native) => {
+                let process_info = report.get_process_info();
+                report.has_process_info() && process_info.has_native() && process_info.get_native() == native
+            },
+            None => true,
+        }
+    }
+}
+
+/// Compare dotted versions like `10.13.6` numerically, with missing components as 0.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |version: &str| -> Vec<u64> {
+        version.trim().split('.').map(|part| part.parse().unwrap_or(0)).collect()
+    };
+    let (a, b) = (parse(a), parse(b));
+    for idx in 0..a.len().max(b.len()) {
+        let ordering = a.get(idx).unwrap_or(&0).cmp(b.get(idx).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// A tag attached by a matching rule.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Tag {
+    pub tag: String,
+    pub note: Option<String>,
+}
+
+/// Declarative rules that tag reports for triage.
+#[derive(Clone, Debug, Default)]
+pub struct TriageRules {
+    rules: Vec<Rule>,
+}
+
+impl TriageRules {
+    /// Parse rules from a JSON array of rule objects.
+    pub fn from_json(json: &str) -> Result<TriageRules, Error> {
+        let specs : Vec<RuleSpec> = serde_json::from_str(json).context(ErrorKind::Rules)?;
+        let rules = specs.into_iter().map(Rule::new).collect::<Result<_, _>>()?;
+        Ok(TriageRules { rules })
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<TriageRules, Error> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).context(ErrorKind::RulesFile(path.to_path_buf()))?;
+        TriageRules::from_json(&json)
+    }
+
+    /// Tags of the rules matching `report`, in the order of the rules. `frames` is the symbolicated crashed thread.
+    pub fn evaluate(&self, report: &CrashReport, frames: &[BacktraceFrame]) -> Vec<Tag> {
+        let facts = Facts {
+            report,
+            crash_type: crash_type(report),
+            images: frames.iter()
+                .filter(|frame| frame.image_path.is_some())
+                .map(|frame| frame.image_name())
+                .collect(),
+            symbols: frames.iter()
+                .flat_map(|frame| frame.symbol.iter().chain(frame.function.iter()))
+                .cloned()
+                .collect(),
+        };
+        self.rules.iter()
+            .filter(|rule| rule.matches(&facts))
+            .map(|rule| Tag {
+                tag: rule.tag.clone(),
+                note: rule.note.clone(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use super::backtrace::image_uuid;
+use super::binary::Binaries;
+use super::protos::crash_report::CrashReport;
+use super::symbolicate::{Region, Symbolicate};
+
+/// A mapped range of memory, as far as the report tells us.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VmRegion {
+    /// Segment name, like `__TEXT` or `__DATA`.
+    pub name: String,
+    pub start: u64,
+    pub end: u64,
+    /// Path of the image the region belongs to.
+    pub image: String,
+}
+
+impl VmRegion {
+    pub fn contains(&self, address: u64) -> bool {
+        self.start <= address && address < self.end
+    }
+
+    pub fn image_name(&self) -> &str {
+        Path::new(&self.image).file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&self.image)
+    }
+}
+
+/// The regions around an address, like the `VM Regions Near` section of Apple's reports.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RegionsNear {
+    pub before: Option<VmRegion>,
+    pub containing: Option<VmRegion>,
+    pub after: Option<VmRegion>,
+}
+
+/// All regions we know of, sorted by address.
+///
+/// Images with a dSYM or a binary are split into their segments. The size PLCrashReporter records for an
+/// image is the size of its `__TEXT` segment, so other images get a single `__TEXT` region.
+pub fn regions(report: &CrashReport, symbolicate: Option<&Symbolicate>, binaries: Option<&Binaries>) -> Vec<VmRegion> {
+    let mut ret = Vec::new();
+    for image in report.get_binary_images() {
+        let base = image.get_base_address();
+        let uuid = image_uuid(image);
+        let segments : Vec<&Region> = match uuid.and_then(|uuid| symbolicate.and_then(|s| s.get(&uuid))) {
+            Some(lookup) if !lookup.segments().is_empty() => lookup.segments().iter().collect(),
+            _ => uuid.and_then(|uuid| binaries.and_then(|b| b.get(&uuid)))
+                .map(|binary| binary.segments())
+                .unwrap_or_default(),
+        };
+        if segments.is_empty() {
+            ret.push(VmRegion {
+                name: String::from("__TEXT"),
+                start: base,
+                end: base + image.get_size(),
+                image: image.get_name().into(),
+            });
+        }
+        for segment in segments {
+            ret.push(VmRegion {
+                name: segment.segment.clone(),
+                start: base + segment.start,
+                end: base + segment.end,
+                image: image.get_name().into(),
+            });
+        }
+    }
+    ret.sort_by_key(|region| region.start);
+    ret
+}
+
+/// The region containing `address` and its neighbours.
+pub fn regions_near(report: &CrashReport, symbolicate: Option<&Symbolicate>, binaries: Option<&Binaries>, address: u64) -> RegionsNear {
+    let regions = regions(report, symbolicate, binaries);
+    let idx = regions.iter().position(|region| region.end > address).unwrap_or(regions.len());
+    let mut ret = RegionsNear::default();
+    if idx > 0 {
+        ret.before = Some(regions[idx - 1].clone());
+    }
+    match regions.get(idx) {
+        Some(region) if region.contains(address) => {
+            ret.containing = Some(region.clone());
+            ret.after = regions.get(idx + 1).cloned();
+        },
+        region => ret.after = region.cloned(),
+    }
+    ret
+}
+
+/// Size in the style of Apple's reports, like `232K` or `1.5M`.
+pub fn format_size(size: u64) -> String {
+    if size < 1 << 20 {
+        format!("{}K", (size + 1023) >> 10)
+    } else if size < 1 << 30 {
+        format!("{:.1}M", size as f64 / (1 << 20) as f64)
+    } else {
+        format!("{:.1}G", size as f64 / (1 << 30) as f64)
+    }
+}
@@ -0,0 +1,169 @@
+extern crate plcrash;
+extern crate uuid;
+extern crate zip;
+
+mod common;
+
+use std::fs::File;
+
+use plcrash::backtrace::{symbolicate_frames, symbolicate_thread, Confidence, Disagreement, SymbolSource};
+use plcrash::machine::CPU_TYPE_ARM64;
+use plcrash::protos::crash_report::CrashReport_Thread_RegisterValue;
+use plcrash::{Options, Symbolicate};
+use uuid::Uuid;
+
+use common::{MachO, CPU_TYPE_X86_64, TEXT_VMADDR};
+
+const METAZ_UUID : &str = "5a537ce0-c887-3373-b4d9-2196436a4f14";
+const METAZ_BASE : u64 = 0x10447a000;
+
+/// A dSYM for MetaZ with `outer` at 0x100-0x200, which inlines `inner` at 0x110-0x118, and a symbol table
+/// that also has `table_only` at 0x400.
+fn metaz_dsym() -> MachO {
+    let abbrev = [
+        1, 0x11, 1, 0x03, 0x08, 0x10, 0x17, 0x11, 0x01, 0x12, 0x01, 0, 0,
+        2, 0x2e, 1, 0x03, 0x08, 0x11, 0x01, 0x12, 0x01, 0, 0,
+        3, 0x2e, 0, 0x03, 0x08, 0x20, 0x0b, 0, 0,
+        4, 0x1d, 0, 0x31, 0x13, 0x11, 0x01, 0x12, 0x01, 0, 0,
+        0,
+    ];
+    let addr = |out: &mut Vec<u8>, offset: u64| out.extend_from_slice(&(TEXT_VMADDR + offset).to_le_bytes());
+
+    let mut info = vec![0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 8];
+    info.push(1);
+    info.extend_from_slice(b"t.c\0");
+    info.extend_from_slice(&[0, 0, 0, 0]);
+    addr(&mut info, 0x100);
+    addr(&mut info, 0x200);
+    let inner = info.len() as u32;
+    info.push(3);
+    info.extend_from_slice(b"inner\0");
+    info.push(1);
+    info.push(2);
+    info.extend_from_slice(b"outer\0");
+    addr(&mut info, 0x100);
+    addr(&mut info, 0x200);
+    info.push(4);
+    info.extend_from_slice(&inner.to_le_bytes());
+    addr(&mut info, 0x110);
+    addr(&mut info, 0x118);
+    info.extend_from_slice(&[0, 0]);
+    let length = info.len() as u32 - 4;
+    info[..4].copy_from_slice(&length.to_le_bytes());
+
+    /* Line 10 from 0x100, line 20 from 0x110 */
+    let header = [1, 1, 0xfb, 14, 13, 0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1, 0, b't', b'.', b'c', 0, 0, 0, 0, 0];
+    let mut program = vec![0, 9, 2];
+    addr(&mut program, 0x100);
+    program.extend_from_slice(&[3, 9, 1, 2, 0x10, 3, 10, 1, 2, 0xf0, 0x01, 0, 1, 1]);
+    let mut line = Vec::new();
+    line.extend_from_slice(&(2 + 4 + header.len() as u32 + program.len() as u32).to_le_bytes());
+    line.extend_from_slice(&[2, 0]);
+    line.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    line.extend_from_slice(&header);
+    line.extend_from_slice(&program);
+
+    MachO::new(METAZ_UUID, CPU_TYPE_X86_64)
+        .section("__DWARF", "__debug_abbrev", TEXT_VMADDR + 0x10000, &abbrev)
+        .section("__DWARF", "__debug_info", TEXT_VMADDR + 0x11000, &info)
+        .section("__DWARF", "__debug_line", TEXT_VMADDR + 0x12000, &line)
+        .symbol("_outer", TEXT_VMADDR + 0x100)
+        .symbol("_table_only", TEXT_VMADDR + 0x400)
+}
+
+#[test]
+fn it_records_symbol_provenance() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let report = plcrash::read_report(&mut report_file).unwrap();
+
+    /* Without client symbols or dSYMs no frame has a name we can trust */
+    let frames = symbolicate_frames(report.get_threads()[0].get_frames(), &report, None, &Options::default()).unwrap();
+    for frame in &frames {
+        assert_eq!(frame.provenance.source, None);
+        assert_eq!(frame.provenance.confidence, Confidence::Low);
+        assert!(frame.provenance.disagreements.is_empty());
+    }
+
+    let disagreement = Disagreement::ClientStart { client_start: 0x1000, function_start: 0x0f80, function_end: 0x1100 };
+    assert_eq!(disagreement.to_string(), "client symbol starts at 0x1000, but the DWARF function spans 0xf80-0x1100");
+}
+
+#[test]
+fn it_recovers_the_caller_from_the_link_register() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    for image in report.mut_binary_images().iter_mut() {
+        image.mut_code_type().set_field_type(CPU_TYPE_ARM64);
+    }
+    let mut lr = CrashReport_Thread_RegisterValue::new();
+    lr.set_name("lr".into());
+    lr.set_value(0x10448a000);
+    report.mut_threads()[0].mut_registers().push(lr);
+
+    let thread = &report.get_threads()[0];
+    let frames = symbolicate_thread(thread, &report, None, &Options::default()).unwrap();
+    assert_eq!(frames.len(), thread.get_frames().len());
+
+    let options = Options { recover_lr_frame: true, ..Options::default() };
+    let frames = symbolicate_thread(thread, &report, None, &options).unwrap();
+    assert_eq!(frames.len(), thread.get_frames().len() + 1);
+    assert!(frames[1].synthetic);
+    assert_eq!((frames[1].index, frames[1].pc), (1, 0x10448a000));
+    assert_eq!((frames[2].index, frames[2].pc), (2, thread.get_frames()[1].get_pc()));
+}
+
+#[test]
+fn it_checks_client_symbols_against_the_dsym() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let path = std::env::temp_dir().join(format!("plcrash-provenance-{}.zip", std::process::id()));
+    metaz_dsym().write_dsym_zip(&path, "MetaZ");
+    let symbolicate = Symbolicate::new(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(symbolicate.get(&Uuid::parse_str(METAZ_UUID).unwrap()).unwrap().function_ranges_error.is_none());
+
+    /* Frames 1-5 in MetaZ, as pc and client symbol offsets from the image base */
+    let frames = [(0x120, Some(("_outer", 0x100))), (0x114, None), (0x130, Some(("_helper", 0x128))), (0x410, None), (0x420, Some(("_other", 0x418)))];
+    for (frame, &(pc, symbol)) in report.mut_threads()[0].mut_frames().iter_mut().skip(1).zip(frames.iter()) {
+        frame.set_pc(METAZ_BASE + pc);
+        frame.clear_symbol();
+        if let Some((name, start)) = symbol {
+            frame.mut_symbol().set_name(name.into());
+            frame.mut_symbol().set_start_address(METAZ_BASE + start);
+        }
+    }
+
+    let thread = &report.get_threads()[0];
+    let frames = symbolicate_thread(thread, &report, Some(&symbolicate), &Options::default()).unwrap();
+
+    /* The client symbol is where the DWARF function starts */
+    assert_eq!((frames[1].symbol.as_ref().unwrap().as_str(), frames[1].symbol_offset), ("outer", Some(0x20)));
+    assert_eq!((frames[1].function.as_ref().unwrap().as_str(), frames[1].function_offset), ("outer", Some(0x20)));
+    assert_eq!((frames[1].provenance.source, frames[1].provenance.confidence), (Some(SymbolSource::Client), Confidence::High));
+
+    /* Inlined code is named after the function the offset is from, but located at its own line */
+    assert_eq!((frames[2].function.as_ref().unwrap().as_str(), frames[2].function_offset), ("outer", Some(0x14)));
+    let location = frames[2].location.as_ref().unwrap();
+    assert!(location.file().unwrap().ends_with("t.c"));
+    assert_eq!(location.line(), Some(20));
+    assert_eq!((frames[2].provenance.source, frames[2].provenance.confidence), (Some(SymbolSource::Dwarf), Confidence::High));
+
+    assert_eq!(frames[3].provenance.disagreements, vec![Disagreement::ClientStart {
+        client_start: METAZ_BASE + 0x128, function_start: METAZ_BASE + 0x100, function_end: METAZ_BASE + 0x200,
+    }]);
+    assert_eq!((frames[3].provenance.source, frames[3].provenance.confidence), (Some(SymbolSource::Client), Confidence::Low));
+
+    /* Outside of any DWARF function the symbol table names the frame */
+    assert_eq!((frames[4].symbol.as_ref().unwrap().as_str(), frames[4].symbol_offset), ("table_only", Some(0x10)));
+    assert_eq!((frames[4].provenance.source, frames[4].provenance.confidence), (Some(SymbolSource::SymbolTable), Confidence::Medium));
+
+    assert_eq!(frames[5].provenance.disagreements, vec![Disagreement::SymbolTableStart {
+        client_start: METAZ_BASE + 0x418, symbol_start: METAZ_BASE + 0x400,
+    }]);
+    assert_eq!(frames[5].provenance.confidence, Confidence::Low);
+
+    let options = Options { prefer_dwarf_names: true, ..Options::default() };
+    let frames = symbolicate_thread(thread, &report, Some(&symbolicate), &options).unwrap();
+    assert_eq!((frames[1].provenance.source, frames[1].provenance.confidence), (Some(SymbolSource::Dwarf), Confidence::High));
+    assert_eq!(frames[5].provenance.source, Some(SymbolSource::Client));
+}
@@ -0,0 +1,100 @@
+extern crate plcrash;
+extern crate uuid;
+extern crate zip;
+
+mod common;
+
+use std::fs::File;
+
+use plcrash::backtrace::symbolicate_thread;
+use plcrash::disasm;
+use plcrash::objc;
+use plcrash::error::ErrorKind;
+use plcrash::protos::crash_report::CrashReport;
+use plcrash::vm_region;
+use plcrash::{Binaries, Options};
+
+use common::{MachO, CPU_TYPE_X86_64, TEXT_VMADDR};
+
+const METAZ_UUID : &str = "5a537ce0-c887-3373-b4d9-2196436a4f14";
+const METAZ_BASE : u64 = 0x10447a000;
+
+#[test]
+fn it_only_disassembles_known_binaries() {
+    let mut binaries = Binaries::new();
+    let err = binaries.add("tests/MetaZ-crash.txt").unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Binary("tests/MetaZ-crash.txt".into()));
+
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let report = plcrash::read_report(&mut report_file).unwrap();
+    assert!(disasm::disassemble(&report, &binaries, None).unwrap().is_none());
+}
+
+#[test]
+fn it_needs_the_binary_to_recover_selectors() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    report.mut_threads()[0].mut_frames()[0].mut_symbol().set_name("_objc_msgSend".into());
+
+    /* rsi points into AppKit's selector names, which we don't have */
+    let selector = objc::msgsend_selector(&report, &Binaries::new(), &symbolicate_thread(&report.get_threads()[0], &report, None, &Options::default()).unwrap());
+    assert_eq!(selector, None);
+}
+
+#[test]
+fn it_reads_selectors_from_the_binary() {
+    let path = std::env::temp_dir().join(format!("plcrash-objc-{}", std::process::id()));
+    MachO::new(METAZ_UUID, CPU_TYPE_X86_64)
+        .section("__TEXT", "__text", TEXT_VMADDR + 0x1000, &[0xc3; 16])
+        .section("__TEXT", "__objc_methname", TEXT_VMADDR + 0x2000, b"init\0setTitle:\0")
+        .write(&path);
+    let mut binaries = Binaries::new();
+    binaries.add(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    report.mut_threads()[0].mut_frames()[0].mut_symbol().set_name("_objc_msgSend".into());
+    let set_rsi = |report: &mut CrashReport, value: u64| {
+        report.mut_threads()[0].mut_registers().iter_mut().find(|reg| reg.get_name() == "rsi").unwrap().set_value(value);
+    };
+    let selector = |report: &CrashReport| {
+        objc::msgsend_selector(report, &binaries, &symbolicate_thread(&report.get_threads()[0], report, None, &Options::default()).unwrap())
+    };
+
+    set_rsi(&mut report, METAZ_BASE + 0x2005);
+    assert_eq!(selector(&report), Some("setTitle:".into()));
+
+    /* Only the selector name section holds selectors */
+    set_rsi(&mut report, METAZ_BASE + 0x1000);
+    assert_eq!(selector(&report), None);
+
+    set_rsi(&mut report, METAZ_BASE + 0x2005);
+    report.mut_threads()[0].mut_frames()[0].mut_symbol().set_name("_objc_release".into());
+    assert_eq!(selector(&report), None);
+}
+
+#[test]
+fn it_splits_images_into_the_segments_of_the_binary() {
+    let path = std::env::temp_dir().join(format!("plcrash-regions-{}", std::process::id()));
+    MachO::new(METAZ_UUID, CPU_TYPE_X86_64)
+        .section("__TEXT", "__text", TEXT_VMADDR + 0x1000, &[0xc3; 16])
+        .section("__DATA", "__data", TEXT_VMADDR + 0x40000, &[0; 0x100])
+        .write(&path);
+    let mut binaries = Binaries::new();
+    binaries.add(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let report = plcrash::read_report(&mut report_file).unwrap();
+
+    /* Without the binary the image is a single __TEXT region */
+    let near = vm_region::regions_near(&report, None, None, METAZ_BASE + 0x40080);
+    assert_eq!(near.before.unwrap().name, "__TEXT");
+    assert_eq!(near.containing, None);
+
+    let near = vm_region::regions_near(&report, None, Some(&binaries), METAZ_BASE + 0x40080);
+    let data = near.containing.unwrap();
+    assert_eq!((data.name.as_str(), data.start, data.end, data.image_name()), ("__DATA", METAZ_BASE + 0x40000, METAZ_BASE + 0x40100, "MetaZ"));
+    assert_eq!(near.before.unwrap().name, "__TEXT");
+}
@@ -0,0 +1,53 @@
+extern crate plcrash;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use plcrash::blame::CommitPattern;
+use plcrash::GitBlame;
+
+fn git(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C").arg(repo)
+        .args(["-c", "user.name=Brian Olsen", "-c", "user.email=brian@maven-group.org"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed", args);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn it_blames_lines_at_the_build_commit() {
+    let repo = std::env::temp_dir().join("plcrash-blame-test");
+    let _ = fs::remove_dir_all(&repo);
+    fs::create_dir_all(repo.join("App/src")).unwrap();
+    git(&repo, &["init", "-q"]);
+    fs::write(repo.join("App/src/MZWriteQueue.m"), "one\ntwo\nthree\n").unwrap();
+    git(&repo, &["add", "-A"]);
+    git(&repo, &["commit", "-q", "-m", "Add write queue"]);
+    let build = git(&repo, &["rev-parse", "HEAD"]).trim().to_string();
+
+    /* A later change must not show up when blaming the build commit */
+    fs::write(repo.join("App/src/MZWriteQueue.m"), "one\nTWO\nthree\n").unwrap();
+    git(&repo, &["commit", "-q", "-a", "-m", "Change write queue"]);
+
+    let blame = GitBlame::open(&repo).unwrap();
+    let version = format!("1.0 (17.10.07.22.{})", &build[..7]);
+    assert_eq!(blame.commit_for_version(&version), Some(build.clone()));
+    assert_eq!(blame.commit_for_version("1.0 (17.10.07.22)"), None);
+
+    let path = blame.resolve(&build, "/Users/bro/Documents/Maven-Group/MetaZ/App/src/MZWriteQueue.m");
+    assert_eq!(path, Some("App/src/MZWriteQueue.m".to_string()));
+
+    let line = blame.blame_line(&build, "App/src/MZWriteQueue.m", 2).unwrap();
+    assert_eq!(line.commit, build);
+    assert_eq!(line.author, "Brian Olsen");
+    assert_eq!(line.author_mail, "brian@maven-group.org");
+    assert_eq!(line.summary, "Add write queue");
+
+    let mut blame = blame;
+    blame.set_commit_pattern(CommitPattern::new(r"\+([0-9a-f]+)$").unwrap());
+    assert_eq!(blame.commit_for_version(&format!("1.0+{}", &build[..7])), Some(build));
+}
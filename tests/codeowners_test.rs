@@ -0,0 +1,73 @@
+extern crate addr2line;
+extern crate plcrash;
+
+use std::fs::File;
+
+use plcrash::backtrace::symbolicate_frames;
+use plcrash::codeowners::{self, RoutingStrategy};
+use plcrash::glob::Glob;
+use plcrash::source::PathMap;
+use plcrash::{CodeOwners, InAppRules, Location, Options};
+
+const OWNERS : &str = "
+# Fallback
+*                   @maven/core
+/MetaZ/Plugins/     @maven/plugins
+*.xib               @maven/design   # layout
+image:MetaZKit      @maven/kit
+";
+
+fn location(file: &str) -> Location {
+    Location(addr2line::Location {
+        file: Some(file.into()),
+        line: Some(10),
+        column: None,
+    })
+}
+
+#[test]
+fn it_matches_paths_like_codeowners() {
+    let mut owners = CodeOwners::parse(OWNERS).unwrap();
+    let rule = owners.owner_of_path("/Users/bro/src/MetaZ/Plugins/TagChimp/TCSearch.m").unwrap();
+    assert_eq!((rule.line, rule.owners.clone()), (4, vec![String::from("@maven/plugins")]));
+    assert_eq!(owners.owner_of_path("/Users/bro/src/MetaZ/Base.lproj/Main.xib").unwrap().owners, vec!["@maven/design"]);
+    assert_eq!(owners.owner_of_path("/Users/bro/src/MetaZ/AppDelegate.m").unwrap().line, 3);
+
+    /* Mapped paths are only tried from the repository root */
+    let mut paths = PathMap::new();
+    paths.add("/Users/bro/src", "Sources");
+    owners.set_path_map(paths);
+    assert_eq!(owners.owner_of_path("/Users/bro/src/MetaZ/Plugins/TagChimp/TCSearch.m").unwrap().line, 3);
+
+    /* Anchored rules all match from the same guessed root, the deepest directory one of them matches at */
+    let owners = CodeOwners::parse("/Plugins/ @maven/plugins\n/MetaZ/ @maven/core\n*.m @maven/objc\n").unwrap();
+    assert_eq!(owners.owner_of_path("/Users/bro/src/MetaZ/Plugins/Loader.h").unwrap().owners, vec!["@maven/plugins"]);
+    assert_eq!(owners.owner_of_path("/Users/bro/src/MetaZ/Core/Loader.h").unwrap().owners, vec!["@maven/core"]);
+    assert_eq!(owners.owner_of_path("/Users/bro/src/MetaZ/Plugins/Loader.m").unwrap().owners, vec!["@maven/objc"]);
+}
+
+#[test]
+fn it_routes_reports_to_owners() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let report = plcrash::read_report(&mut report_file).unwrap();
+    let owners = CodeOwners::parse(OWNERS).unwrap();
+    let in_app = InAppRules { include_paths: vec![Glob::new("*/libdyld.dylib").unwrap()], ..InAppRules::default() };
+    let mut frames = symbolicate_frames(report.get_threads()[0].get_frames(), &report, None, &Options::default()).unwrap();
+    frames[1].location = Some(location("/Users/bro/src/MetaZ/AppDelegate.m"));
+    frames[2].location = Some(location("/Users/bro/src/MetaZ/Plugins/Loader.m"));
+    frames[3].location = Some(location("/Users/bro/src/MetaZ/Plugins/Registry.m"));
+    frames[12].location = Some(location("/Users/bro/src/MetaZ/Plugins/main.m"));
+
+    let routing = codeowners::route(&frames, &report, &in_app, &owners, RoutingStrategy::Culprit).unwrap();
+    assert_eq!((routing.owners.clone(), routing.frame), (vec![String::from("@maven/core")], 1));
+
+    /* 1/2 + 1/3 + 1/4 beats 1 */
+    let routing = codeowners::route(&frames, &report, &in_app, &owners, RoutingStrategy::Vote).unwrap();
+    assert_eq!((routing.owners.clone(), routing.frame, routing.rule.line), (vec![String::from("@maven/plugins")], 2, 4));
+    assert_eq!(routing.votes.len(), 2);
+    let lines : Vec<usize> = routing.votes[0].rules.iter().map(|rule| rule.line).collect();
+    assert_eq!(lines, vec![4]);
+    assert_eq!(routing.votes[1].rules[0].line, 3);
+
+    assert!(CodeOwners::parse("[broken @team").is_ok());
+}
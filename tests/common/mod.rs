@@ -0,0 +1,192 @@
+//! Mach-O images and zipped dSYMs for tests, built in memory.
+
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use uuid::Uuid;
+
+/// Where the `__TEXT` segment of test images is linked.
+pub const TEXT_VMADDR : u64 = 0x1_0000_0000;
+
+pub const CPU_TYPE_X86_64 : u32 = 0x0100_0007;
+pub const CPU_TYPE_ARM64 : u32 = 0x0100_000c;
+
+const MH_MAGIC_64 : u32 = 0xfeed_facf;
+const MH_EXECUTE : u32 = 0x2;
+const LC_SYMTAB : u32 = 0x2;
+const LC_SEGMENT_64 : u32 = 0x19;
+const LC_UUID : u32 = 0x1b;
+const N_SECT : u8 = 0xe;
+
+struct Section {
+    segment: String,
+    name: String,
+    addr: u64,
+    data: Vec<u8>,
+}
+
+/// A thin 64-bit Mach-O image with sections at fixed addresses and a symbol table.
+pub struct MachO {
+    uuid: Uuid,
+    cpu_type: u32,
+    sections: Vec<Section>,
+    symbols: Vec<(String, u64)>,
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_name(out: &mut Vec<u8>, name: &str) {
+    let mut bytes = [0u8; 16];
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+    out.extend_from_slice(&bytes);
+}
+
+impl MachO {
+    pub fn new(uuid: &str, cpu_type: u32) -> MachO {
+        MachO {
+            uuid: Uuid::parse_str(uuid).unwrap(),
+            cpu_type,
+            sections: Vec::new(),
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Add a section linked at `addr`. Sections of a segment have to be added in address order.
+    pub fn section(mut self, segment: &str, name: &str, addr: u64, data: &[u8]) -> MachO {
+        self.sections.push(Section {
+            segment: segment.into(),
+            name: name.into(),
+            addr,
+            data: data.to_vec(),
+        });
+        self
+    }
+
+    /// Add a symbol table entry, with the platform `_` prefix.
+    pub fn symbol(mut self, name: &str, addr: u64) -> MachO {
+        self.symbols.push((name.into(), addr));
+        self
+    }
+
+    fn sections_of(&self, segment: &str) -> Vec<&Section> {
+        self.sections.iter().filter(|section| section.segment == segment).collect()
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        /* Segments as name, start and end, with __TEXT always first and linked at TEXT_VMADDR */
+        let mut segments : Vec<(&str, u64, u64)> = vec![("__TEXT", TEXT_VMADDR, TEXT_VMADDR)];
+        for section in &self.sections {
+            let end = section.addr + section.data.len() as u64;
+            match segments.iter_mut().find(|segment| segment.0 == section.segment) {
+                Some(segment) => segment.2 = segment.2.max(end),
+                None => segments.push((&section.segment, section.addr, end)),
+            }
+        }
+
+        let sizeofcmds : usize = 24 + 24 + segments.iter().map(|segment| 72 + 80 * self.sections_of(segment.0).len()).sum::<usize>();
+        let data_start = ((32 + sizeofcmds + 15) & !15) as u64;
+
+        let mut commands = Vec::new();
+        push_u32(&mut commands, LC_UUID);
+        push_u32(&mut commands, 24);
+        commands.extend_from_slice(self.uuid.as_bytes());
+
+        let mut fileoff = data_start;
+        let mut data = Vec::new();
+        for &(name, start, end) in &segments {
+            let sections = self.sections_of(name);
+            push_u32(&mut commands, LC_SEGMENT_64);
+            push_u32(&mut commands, 72 + 80 * sections.len() as u32);
+            push_name(&mut commands, name);
+            push_u64(&mut commands, start);
+            push_u64(&mut commands, end - start);
+            push_u64(&mut commands, fileoff);
+            push_u64(&mut commands, end - start);
+            push_u32(&mut commands, 7);
+            push_u32(&mut commands, 7);
+            push_u32(&mut commands, sections.len() as u32);
+            push_u32(&mut commands, 0);
+
+            let mut contents = vec![0u8; (end - start) as usize];
+            for section in sections {
+                let offset = section.addr - start;
+                contents[offset as usize..offset as usize + section.data.len()].copy_from_slice(&section.data);
+                push_name(&mut commands, &section.name);
+                push_name(&mut commands, name);
+                push_u64(&mut commands, section.addr);
+                push_u64(&mut commands, section.data.len() as u64);
+                push_u32(&mut commands, (fileoff + offset) as u32);
+                for _ in 0..7 {
+                    push_u32(&mut commands, 0);
+                }
+            }
+            fileoff += contents.len() as u64;
+            data.extend(contents);
+        }
+
+        let mut strings = vec![0u8];
+        let mut nlists = Vec::new();
+        for (name, addr) in &self.symbols {
+            push_u32(&mut nlists, strings.len() as u32);
+            nlists.push(N_SECT);
+            nlists.push(1);
+            nlists.extend_from_slice(&[0, 0]);
+            push_u64(&mut nlists, *addr);
+            strings.extend_from_slice(name.as_bytes());
+            strings.push(0);
+        }
+        push_u32(&mut commands, LC_SYMTAB);
+        push_u32(&mut commands, 24);
+        push_u32(&mut commands, fileoff as u32);
+        push_u32(&mut commands, self.symbols.len() as u32);
+        push_u32(&mut commands, (fileoff + nlists.len() as u64) as u32);
+        push_u32(&mut commands, strings.len() as u32);
+        data.extend(nlists);
+        data.extend(strings);
+
+        let mut out = Vec::new();
+        push_u32(&mut out, MH_MAGIC_64);
+        push_u32(&mut out, self.cpu_type);
+        push_u32(&mut out, 3);
+        push_u32(&mut out, MH_EXECUTE);
+        push_u32(&mut out, segments.len() as u32 + 2);
+        push_u32(&mut out, sizeofcmds as u32);
+        push_u32(&mut out, 0);
+        push_u32(&mut out, 0);
+        out.extend(commands);
+        out.resize(data_start as usize, 0);
+        out.extend(data);
+        out
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) {
+        std::fs::write(path, self.build()).unwrap();
+    }
+
+    /// Write the image as the DWARF file of a zipped `<name>.app.dSYM`, the way `Symbolicate::new` reads it.
+    pub fn write_dsym_zip<P: AsRef<Path>>(&self, path: P, name: &str) {
+        let bundle = format!("{}.app.dSYM/", name);
+        let options = zip::write::FileOptions::default();
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        zip.add_directory(bundle.clone(), options).unwrap();
+        zip.start_file(format!("{}Contents/Info.plist", bundle), options).unwrap();
+        zip.write_all(format!(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<plist version=\"1.0\"><dict>",
+            "<key>CFBundleIdentifier</key><string>com.apple.xcode.dsym.{}</string>",
+            "<key>CFBundleVersion</key><string>1</string>",
+            "</dict></plist>\n"), name).as_bytes()).unwrap();
+        zip.start_file(format!("{}Contents/Resources/DWARF/{}", bundle, name), options).unwrap();
+        zip.write_all(&self.build()).unwrap();
+        zip.finish().unwrap();
+    }
+}
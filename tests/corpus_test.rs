@@ -0,0 +1,45 @@
+extern crate plcrash;
+
+use std::fs::File;
+
+use plcrash::{Corpus, Options};
+
+#[test]
+fn it_groups_reports_by_fingerprint() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let options = Options::default();
+    let mut corpus = Corpus::default();
+
+    let hash = corpus.add_file("tests/MetaZ.plcrash", None, &options).unwrap().fingerprint.hash.clone();
+    report.mut_application_info().set_version("17.11\r01".into());
+    let timestamp = report.get_system_info().get_timestamp();
+    report.mut_system_info().set_timestamp(timestamp + 86400);
+    corpus.add(&report, None, &options).unwrap();
+    report.mut_signal().set_name("SIGBUS".into());
+    corpus.add(&report, None, &options).unwrap();
+    assert!(corpus.add_file("tests/MetaZ-crash.txt", None, &options).is_err());
+
+    assert_eq!(corpus.reports(), 3);
+    let groups = corpus.groups();
+    assert_eq!(groups.len(), 2);
+    let group = groups[0];
+    assert_eq!(group.fingerprint.hash, hash);
+    assert_eq!(group.count, 2);
+    assert_eq!(group.versions.get("17.10.07.22.ec9e94c"), Some(&1));
+    assert_eq!(group.versions.get("17.11\r01"), Some(&1));
+    assert_eq!(group.last_seen.unwrap() - group.first_seen.unwrap(), 86400);
+
+    let csv = corpus.to_csv();
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().starts_with("hash,count,crash_type,"));
+    let row = lines.next().unwrap();
+    assert!(row.starts_with(&format!("{},2,SIGSEGV/SEGV_MAPERR,", hash)));
+    assert!(row.contains(&format!(",{},{},", timestamp, timestamp + 86400)));
+    assert!(row.contains(",\"17.10.07.22.ec9e94c=1;17.11\r01=1\","));
+    assert_eq!(lines.count(), 1);
+
+    let json = corpus.to_json().unwrap();
+    assert!(json.contains("\"reports\": 3"));
+    assert!(json.contains(&format!("\"first_seen\": {},", timestamp)));
+}
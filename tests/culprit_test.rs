@@ -0,0 +1,51 @@
+extern crate plcrash;
+
+use std::fs::File;
+
+use plcrash::backtrace::symbolicate_frames;
+use plcrash::culprit::{culprit, InAppRules};
+use plcrash::glob::Glob;
+use plcrash::image_kind::{ImageClassifier, Sdk};
+use plcrash::Options;
+
+#[test]
+fn it_selects_the_first_in_app_frame() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    {
+        let frame = &mut report.mut_threads()[0].mut_frames()[1];
+        let pc = frame.get_pc();
+        frame.mut_symbol().set_name("_MZAssertionFailed".into());
+        frame.mut_symbol().set_start_address(pc - 0x10);
+    }
+    let frames = symbolicate_frames(report.get_threads()[0].get_frames(), &report, None, &Options::default()).unwrap();
+
+    let rules = InAppRules::default();
+    assert_eq!(culprit(&frames, &report, &rules).map(|frame| frame.index), Some(1));
+
+    let rules = InAppRules { exclude_symbols: vec![Glob::new("MZAssert*").unwrap()], ..InAppRules::default() };
+    assert_eq!(culprit(&frames, &report, &rules).map(|frame| frame.index), Some(2));
+
+    let rules = InAppRules { exclude_paths: vec![Glob::new("*.app/Contents/MacOS/*").unwrap()], ..InAppRules::default() };
+    assert_eq!(culprit(&frames, &report, &rules).map(|frame| frame.index), None);
+
+    let rules = InAppRules {
+        main_executable: false,
+        bundle_prefixes: vec![String::from("com.example.")],
+        include_paths: vec![Glob::new("/System/Library/Frameworks/AppKit.framework/*").unwrap()],
+        ..InAppRules::default()
+    };
+    assert_eq!(culprit(&frames, &report, &rules).map(|frame| frame.index), Some(4));
+
+    /* Images the rules classify themselves go by their classifier */
+    let sdk = "/Users/bro/Documents/Maven-Group/MetaZ/build/Release/MetaZ.app/Contents/Frameworks/Sparkle.framework/Versions/A/Sparkle";
+    assert!(InAppRules::default().is_in_app_image(&report, sdk));
+    let rules = InAppRules {
+        classifier: ImageClassifier::new(vec![Sdk { name: "Sparkle".into(), bundle_id: Some("org.sparkle-project.Sparkle".into()), paths: Vec::new() }]),
+        ..InAppRules::default()
+    };
+    assert!(!rules.is_in_app_image(&report, sdk));
+
+    assert!(Glob::new("*.app/Frameworks/*").unwrap().is_match("/Applications/MetaZ.app/Frameworks/MetaZKit.framework/MetaZKit"));
+    assert!(!Glob::new("lib?.dylib").unwrap().is_match("/usr/lib/libc.dylib"));
+}
@@ -0,0 +1,105 @@
+extern crate plcrash;
+extern crate uuid;
+extern crate zip;
+
+mod common;
+
+use std::fs::File;
+
+use plcrash::arch::Arch;
+use plcrash::disasm;
+use plcrash::protos::crash_report::CrashReport_Thread_RegisterValue;
+use plcrash::{Binaries, Options};
+
+use common::{MachO, CPU_TYPE_X86_64, TEXT_VMADDR};
+
+const METAZ_UUID : &str = "5a537ce0-c887-3373-b4d9-2196436a4f14";
+const METAZ_BASE : u64 = 0x10447a000;
+
+/// push rbp; mov rbp, rsp; mov eax, dword ptr [rdi + 0x18]; pop rbp; ret
+const X86_64_CODE : [u8; 10] = [0x55, 0x48, 0x89, 0xe5, 0x8b, 0x47, 0x18, 0x5d, 0xc3, 0x90];
+
+fn registers(values: &[(&str, u64)]) -> Vec<CrashReport_Thread_RegisterValue> {
+    values.iter()
+        .map(|&(name, value)| {
+            let mut register = CrashReport_Thread_RegisterValue::new();
+            register.set_name(name.into());
+            register.set_value(value);
+            register
+        })
+        .collect()
+}
+
+#[test]
+fn it_annotates_the_faulting_x86_64_instruction() {
+    let registers = registers(&[("rax", 0x1_0000_0005), ("rdi", 0), ("rbp", 0x7000)]);
+    let instructions = disasm::decode(Arch::X86_64, &X86_64_CODE, 0x1000, 0x1004, &registers, Some(0x18)).unwrap().unwrap();
+
+    let decoded : Vec<String> = instructions.iter().map(|insn| format!("{} {}", insn.mnemonic, insn.operands)).collect();
+    assert_eq!(&decoded[..5], &["push rbp", "mov rbp, rsp", "mov eax, dword ptr [rdi + 0x18]", "pop rbp", "ret "]);
+    let faulting : Vec<u64> = instructions.iter().filter(|insn| insn.faulting).map(|insn| insn.address).collect();
+    assert_eq!(faulting, vec![0x1004]);
+    assert_eq!(instructions[2].bytes, vec![0x8b, 0x47, 0x18]);
+    assert_eq!(instructions[2].annotations, vec!["eax = 0x5", "rdi = 0x0", "[rdi + 0x18] is the fault address 0x18"]);
+    assert!(instructions[1].annotations.is_empty());
+
+    /* No instruction starts in the middle of one */
+    assert!(disasm::decode(Arch::X86_64, &X86_64_CODE, 0x1000, 0x1005, &registers, Some(0x18)).unwrap().is_none());
+    assert!(disasm::decode(Arch::Arm, &X86_64_CODE, 0x1000, 0x1004, &registers, Some(0x18)).unwrap().is_none());
+}
+
+#[test]
+fn it_annotates_the_faulting_arm64_instruction() {
+    /* stp x29, x30, [sp, #-0x10]!; mov x29, sp; ldr w0, [x29, #0xc]; ldp x29, x30, [sp], #0x10; ret */
+    let code = [
+        0xfd, 0x7b, 0xbf, 0xa9, 0xfd, 0x03, 0x00, 0x91, 0xa0, 0x0f, 0x40, 0xb9, 0xfd, 0x7b, 0xc1, 0xa8, 0xc0, 0x03, 0x5f, 0xd6,
+    ];
+    let registers = registers(&[("x0", 0xffff_0000_0000_0001), ("fp", 0x1000)]);
+    let instructions = disasm::decode(Arch::Arm64, &code, 0x2000, 0x2008, &registers, Some(0x100c)).unwrap().unwrap();
+
+    assert_eq!(instructions.len(), 5);
+    assert_eq!(instructions.iter().position(|insn| insn.faulting), Some(2));
+    assert_eq!((instructions[2].mnemonic.as_str(), instructions[2].operands.as_str()), ("ldr", "w0, [x29, #0xc]"));
+    assert_eq!(instructions[2].annotations, vec!["w0 = 0x1", "x29 = 0x1000", "[x29, #0xc] is the fault address 0x100c"]);
+
+    let annotations = &disasm::decode(Arch::Arm64, &code, 0x2000, 0x2008, &registers, Some(0x18)).unwrap().unwrap()[2].annotations;
+    assert_eq!(annotations.len(), 2);
+}
+
+#[test]
+fn it_says_when_the_function_start_is_unknown() {
+    let path = std::env::temp_dir().join(format!("plcrash-disasm-{}", std::process::id()));
+    MachO::new(METAZ_UUID, CPU_TYPE_X86_64)
+        .section("__TEXT", "__text", TEXT_VMADDR + 0x1000, &X86_64_CODE)
+        .write(&path);
+    let mut binaries = Binaries::new();
+    binaries.add(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let pc = METAZ_BASE + 0x1004;
+    report.mut_threads()[0].mut_frames()[0].set_pc(pc);
+    report.mut_threads()[0].mut_frames()[0].clear_symbol();
+    for register in report.mut_threads()[0].mut_registers().iter_mut() {
+        match register.get_name() {
+            "rip" => register.set_value(pc),
+            "rdi" => register.set_value(0),
+            "rax" => register.set_value(5),
+            _ => {},
+        }
+    }
+    let options = Options { binaries: Some(&binaries), disassemble: true, ..Options::default() };
+
+    /* Without a symbol x86 decoding can only start at the pc */
+    let text = plcrash::text_report_with_options(&report, None, &options).unwrap();
+    assert!(text.contains(concat!(
+        "Disassembly (MetaZ, from the crashed pc as the function start is unknown):\n",
+        "--> 0x000000010447b004  mov      eax, dword ptr [rdi + 0x18]    ; eax = 0x5, rdi = 0x0, [rdi + 0x18] is the fault address 0x18\n",
+    )), "{}", text);
+
+    report.mut_threads()[0].mut_frames()[0].mut_symbol().set_name("_load".into());
+    report.mut_threads()[0].mut_frames()[0].mut_symbol().set_start_address(METAZ_BASE + 0x1000);
+    let text = plcrash::text_report_with_options(&report, None, &options).unwrap();
+    assert!(text.contains("Disassembly (MetaZ):\n    0x000000010447b000  push     rbp\n"), "{}", text);
+}
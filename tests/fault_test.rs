@@ -0,0 +1,49 @@
+extern crate plcrash;
+
+use std::fs::File;
+
+use plcrash::fault::{self, FaultKind};
+use plcrash::vm_region::{self, format_size};
+
+#[test]
+fn it_classifies_fault_addresses() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+
+    let fault = fault::classify(&report, None).unwrap();
+    assert_eq!(fault.kind, FaultKind::NearNull { offset: 0x18 });
+    assert_eq!(fault.to_string(), "NULL + 0x18, likely a field access on a nil pointer");
+
+    report.mut_signal().set_address(0);
+    assert_eq!(fault::classify(&report, None).unwrap().kind, FaultKind::Null);
+
+    /* Frame 1 is in the MetaZ image */
+    report.mut_signal().set_address(0x104489574);
+    match fault::classify(&report, None).unwrap().kind {
+        FaultKind::Image { image, offset, .. } => {
+            assert!(image.ends_with("/MetaZ"));
+            assert_eq!(offset, 0x104489574 - 0x10447a000);
+        },
+        kind => panic!("unexpected {:?}", kind),
+    }
+
+    let sp = report.get_threads()[0].get_registers().iter().find(|reg| reg.get_name() == "rsp").unwrap().get_value();
+    report.mut_signal().set_address(sp - 0x100);
+    assert_eq!(fault::classify(&report, None).unwrap().kind, FaultKind::Stack { stack_pointer: sp, distance: -0x100 });
+
+    report.mut_signal().set_address(0x4141_4141_4141);
+    assert_eq!(fault::classify(&report, None).unwrap().kind, FaultKind::Wild);
+
+    let near = vm_region::regions_near(&report, None, None, 0x18);
+    assert_eq!(near.before, None);
+    assert_eq!(near.containing, None);
+    let after = near.after.unwrap();
+    assert_eq!((after.name.as_str(), after.start, after.image_name()), ("__TEXT", 0x10447a000, "MetaZ"));
+    assert_eq!(format_size(after.end - after.start), "232K");
+
+    let near = vm_region::regions_near(&report, None, None, 0x104489574);
+    assert_eq!(near.containing.unwrap().image_name(), "MetaZ");
+
+    report.mut_signal().set_name("SIGABRT".into());
+    assert_eq!(fault::classify(&report, None), None);
+}
@@ -0,0 +1,46 @@
+extern crate plcrash;
+
+use std::fs::File;
+
+use plcrash::fingerprint::{self, FingerprintOptions};
+use plcrash::Options;
+
+#[test]
+fn it_fingerprints_by_name() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let options = Options::default();
+    let fingerprint_options = FingerprintOptions::default();
+
+    /* None of the frames have a name */
+    let bare = fingerprint::fingerprint(&report, None, &options, &fingerprint_options).unwrap();
+    assert_eq!(bare.crash_type, "SIGSEGV/SEGV_MAPERR");
+    assert!(bare.frames.is_empty());
+
+    for &(idx, name) in &[(0, "_objc_msgSend"), (1, "_-[MZWriteQueue start]"), (4, "_-[NSApplication run]")] {
+        let frame = &mut report.mut_threads()[0].mut_frames()[idx];
+        let pc = frame.get_pc();
+        frame.mut_symbol().set_name(name.into());
+        frame.mut_symbol().set_start_address(pc - 0x10);
+    }
+    let named = fingerprint::fingerprint(&report, None, &options, &fingerprint_options).unwrap();
+    assert_eq!(named.components(), vec!["SIGSEGV/SEGV_MAPERR", "MetaZ`-[MZWriteQueue start]"]);
+    assert_eq!(named.frames[0].index, 1);
+    assert_ne!(named.hash, bare.hash);
+
+    /* A new build moves code around without changing the fingerprint */
+    {
+        let frame = &mut report.mut_threads()[0].mut_frames()[1];
+        let pc = frame.get_pc() + 0x40;
+        frame.set_pc(pc);
+        frame.mut_symbol().set_start_address(pc - 0x30);
+    }
+    assert_eq!(fingerprint::fingerprint(&report, None, &options, &fingerprint_options).unwrap().hash, named.hash);
+
+
+    /* Without in-app names the top system frames are used */
+    report.mut_threads()[0].mut_frames()[1].clear_symbol();
+    let fallback = fingerprint::fingerprint(&report, None, &options, &fingerprint_options).unwrap();
+    assert_eq!(fallback.components(), vec!["SIGSEGV/SEGV_MAPERR", "libobjc.A.dylib`objc_msgSend", "AppKit`-[NSApplication run]"]);
+    assert!(!fallback.frames[0].in_app);
+}
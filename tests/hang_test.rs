@@ -0,0 +1,107 @@
+extern crate plcrash;
+extern crate protobuf;
+
+use std::fs::File;
+
+use plcrash::backtrace::symbolicate_threads;
+use plcrash::hang::{self, ThreadActivity};
+use plcrash::machine::CPU_TYPE_ARM64;
+use plcrash::protos::crash_report::{CrashReport, CrashReport_Thread_RegisterValue};
+use plcrash::Options;
+use protobuf::RepeatedField;
+
+fn set_symbols(report: &mut CrashReport, thread: usize, names: &[&str]) {
+    for (frame, name) in report.mut_threads()[thread].mut_frames().iter_mut().zip(names) {
+        let pc = frame.get_pc();
+        frame.mut_symbol().set_name(name.to_string());
+        frame.mut_symbol().set_start_address(pc - 0x10);
+    }
+}
+
+fn set_register(report: &mut CrashReport, thread: usize, name: &str, value: u64) {
+    let registers = report.mut_threads()[thread].mut_registers();
+    *registers = RepeatedField::from_vec(registers.iter().filter(|register| register.get_name() != name).cloned().collect());
+    let mut register = CrashReport_Thread_RegisterValue::new();
+    register.set_name(name.into());
+    register.set_value(value);
+    registers.push(register);
+}
+
+#[test]
+fn it_recognizes_hang_reports() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+
+    /* Killed, but not by the watchdog */
+    report.mut_signal().set_name("SIGKILL".into());
+    report.mut_signal().clear_mach_exception();
+    assert!(!hang::is_hang_report(&report));
+
+    report.mut_signal().mut_mach_exception().set_field_type(10);
+    report.mut_signal().mut_mach_exception().set_codes(vec![0x8bad_f00d]);
+    assert!(hang::is_hang_report(&report));
+}
+
+#[test]
+fn it_analyzes_blocked_threads() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let options = Options::default();
+    assert!(!hang::is_hang_report(&report));
+
+    report.mut_report_info().set_user_requested(true);
+    report.mut_threads()[0].set_crashed(false);
+    set_symbols(&mut report, 0, &["___psynch_mutexwait", "__pthread_mutex_firstfit_lock_slow"]);
+    set_symbols(&mut report, 1, &["___psynch_mutexwait", "__pthread_mutex_firstfit_lock_slow"]);
+    set_symbols(&mut report, 2, &["_mach_msg_trap", "___CFRunLoopServiceMachPort"]);
+    set_symbols(&mut report, 3, &["___ulock_wait", "___DISPATCH_WAIT_FOR_QUEUE__"]);
+    set_register(&mut report, 0, "rdi", 0x6000_0001_0000);
+    set_register(&mut report, 1, "rdi", 0x6000_0002_0000);
+    /* __ulock_wait takes the operation first and the address second */
+    set_register(&mut report, 3, "rdi", 0x0100_0002);
+    set_register(&mut report, 3, "rsi", 0x6000_0003_0000);
+    assert!(hang::is_hang_report(&report));
+
+    let analysis = hang::analyze(&report, &symbolicate_threads(&report, None, &options).unwrap());
+    let activities : Vec<ThreadActivity> = analysis.threads.iter().take(4).map(|thread| thread.activity).collect();
+    assert_eq!(activities, vec![ThreadActivity::MutexWait, ThreadActivity::MutexWait, ThreadActivity::RunLoopIdle, ThreadActivity::DispatchSync]);
+    assert!(analysis.main_thread_blocked);
+    assert_eq!(analysis.threads[3].wait_object, Some(0x6000_0003_0000));
+    let lock_waits = analysis.lock_waits.unwrap();
+    assert_eq!(lock_waits.threads, vec![0, 1, 3]);
+    assert_eq!(lock_waits.locks, vec![0x6000_0001_0000, 0x6000_0002_0000, 0x6000_0003_0000]);
+
+    let text = plcrash::text_report(&report, None).unwrap();
+    assert!(text.contains("Hang Analysis:\nMain thread: blocked\n   Thread 0: waiting for a mutex in __psynch_mutexwait (frame 0) on 0x600000010000\n"));
+    assert!(text.contains("Threads blocked on locks: 0, 1, 3 (on 0x600000010000, 0x600000020000, 0x600000030000)\n"));
+
+    /* Something still running might release the locks */
+    set_symbols(&mut report, 4, &["-[MZWriteQueue loop]"]);
+    assert!(hang::analyze(&report, &symbolicate_threads(&report, None, &options).unwrap()).lock_waits.is_none());
+}
+
+#[test]
+fn it_reads_the_wait_object_of_each_call() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    for image in report.mut_binary_images().iter_mut() {
+        image.mut_code_type().set_field_type(CPU_TYPE_ARM64);
+    }
+    report.mut_report_info().set_user_requested(true);
+    set_symbols(&mut report, 0, &["___ulock_wait2", "__os_unfair_lock_lock_slow"]);
+    set_symbols(&mut report, 1, &["_semaphore_wait_trap", "__dispatch_sema4_wait"]);
+    set_symbols(&mut report, 2, &["___psynch_cvwait", "__pthread_cond_wait"]);
+    set_symbols(&mut report, 3, &["_start_wqthread", "___psynch_mutexwait"]);
+    for thread in 0..4 {
+        set_register(&mut report, thread, "x0", 0x0100_0002);
+        set_register(&mut report, thread, "x1", 0x6000_0000_0010 + thread as u64);
+    }
+
+    let analysis = hang::analyze(&report, &symbolicate_threads(&report, None, &Options::default()).unwrap());
+    let objects : Vec<Option<u64>> = analysis.threads.iter().take(4).map(|thread| thread.wait_object).collect();
+    assert_eq!(objects, vec![Some(0x6000_0000_0010), Some(0x0100_0002), None, None]);
+    assert_eq!(analysis.threads[3].activity, ThreadActivity::Busy);
+
+    /* A single thread waiting for a lock isn't listed */
+    assert!(analysis.lock_waits.is_none());
+}
@@ -0,0 +1,38 @@
+extern crate plcrash;
+
+use std::fs::File;
+
+use plcrash::backtrace::symbolicate_frames;
+use plcrash::culprit::{culprit, InAppRules};
+use plcrash::glob::Glob;
+use plcrash::image_kind::{ImageClassifier, ImageKind, Sdk};
+use plcrash::Options;
+
+#[test]
+fn it_classifies_images() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let report = plcrash::read_report(&mut report_file).unwrap();
+    let classifier = ImageClassifier::new(vec![
+        Sdk { name: "Sparkle".into(), bundle_id: Some("org.sparkle-project.Sparkle".into()), paths: Vec::new() },
+        Sdk { name: "PLCrashReporter".into(), bundle_id: None, paths: vec![Glob::new("*/CrashReporter.framework/*").unwrap()] },
+    ]);
+    let app = "/Users/bro/Documents/Maven-Group/MetaZ/build/Release/MetaZ.app/Contents";
+
+    assert_eq!(classifier.classify(&report, &format!("{}/MacOS/MetaZ", app)), ImageKind::MainExecutable);
+    assert_eq!(classifier.classify(&report, &format!("{}/Frameworks/MetaZKit.framework/Versions/A/MetaZKit", app)), ImageKind::AppFramework);
+    assert_eq!(classifier.classify(&report, &format!("{}/Frameworks/Sparkle.framework/Versions/A/Sparkle", app)), ImageKind::ThirdPartySdk { name: "Sparkle".into() });
+    assert_eq!(classifier.classify(&report, "/Applications/Other.app/Frameworks/Sparkle.framework/Sparkle"), ImageKind::ThirdPartySdk { name: "Sparkle".into() });
+    assert_eq!(classifier.classify(&report, &format!("{}/Resources/Sparkle", app)), ImageKind::AppFramework);
+    assert_eq!(classifier.classify(&report, "/usr/local/bin/Sparkle"), ImageKind::Other);
+    assert_eq!(classifier.classify(&report, &format!("{}/Frameworks/CrashReporter.framework/Versions/A/CrashReporter", app)), ImageKind::ThirdPartySdk { name: "PLCrashReporter".into() });
+    assert_eq!(classifier.classify(&report, "/usr/lib/libobjc.A.dylib"), ImageKind::System);
+    assert_eq!(classifier.classify(&report, "/Library/Developer/CoreSimulator/Profiles/Runtimes/iOS 12.0.simruntime/Contents/Resources/RuntimeRoot/usr/lib/libobjc.A.dylib"), ImageKind::SimulatorRuntime);
+    assert_eq!(classifier.classify(&report, "/usr/local/lib/libfoo.dylib"), ImageKind::Other);
+
+    /* Frames inherit the kind of their image, and in-app rules go by it */
+    let frames = symbolicate_frames(report.get_threads()[0].get_frames(), &report, None, &Options::default()).unwrap();
+    assert_eq!(frames[0].image_kind, Some(ImageKind::System));
+    assert_eq!(frames[1].image_kind, Some(ImageKind::MainExecutable));
+    let rules = InAppRules { main_executable: false, ..InAppRules::default() };
+    assert_eq!(culprit(&frames, &report, &rules).map(|frame| frame.index), None);
+}
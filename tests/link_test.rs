@@ -0,0 +1,55 @@
+extern crate addr2line;
+extern crate plcrash;
+
+use std::fs::File;
+
+use plcrash::{Location, LinkTemplate, Options, PathMap};
+
+#[test]
+fn it_links_locations_to_the_build_commit() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let report = plcrash::read_report(&mut report_file).unwrap();
+
+    let mut paths = PathMap::new();
+    paths.add("/Users/bro/Documents/Maven-Group/MetaZ", "");
+    let mut links = LinkTemplate::new("https://git.example/{repo}/blob/{commit}/{path}#L{line}", "griff/MetaZ");
+    links.set_path_map(paths);
+
+    let location = Location(addr2line::Location {
+        file: Some("/Users/bro/Documents/Maven-Group/MetaZ/App/src/MZWriteQueue.m".into()),
+        line: Some(408),
+        column: Some(5),
+    });
+    assert_eq!(links.link(&report, &location, &Options::default()),
+        Some("https://git.example/griff/MetaZ/blob/ec9e94c/App/src/MZWriteQueue.m#L408".into()));
+
+    assert_eq!(links.format("ec9e94c", "App/My Queue#1.m", 1, None),
+        "https://git.example/griff/MetaZ/blob/ec9e94c/App/My%20Queue%231.m#L1");
+}
+
+#[test]
+fn it_escapes_html_reports() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let report = plcrash::read_report(&mut report_file).unwrap();
+    let html = plcrash::html_report(&report, None, &Options::default()).unwrap();
+    assert!(html.contains("+MetaZ x86_64  &lt;5a537ce0-c887-3373-b4d9-2196436a4f14&gt;"));
+    assert_eq!(html.matches("<pre>").count(), 1);
+}
+
+#[test]
+fn it_fences_markdown_reports() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let markdown = plcrash::markdown_report(&report, None, &Options::default()).unwrap();
+    assert!(markdown.starts_with("```\nIncident Identifier:"));
+    assert!(markdown.ends_with("\n```\n"));
+    assert!(!markdown.contains("<pre>"));
+    assert!(markdown.contains("+MetaZ x86_64  <5a537ce0-c887-3373-b4d9-2196436a4f14>"));
+
+    /* A fence in the report can't close the code block */
+    report.mut_exception().set_name("MZParseException".into());
+    report.mut_exception().set_reason("Unexpected ``` in template".into());
+    let markdown = plcrash::markdown_report(&report, None, &Options::default()).unwrap();
+    assert!(markdown.starts_with("````\n"));
+    assert!(markdown.ends_with("\n````\n"));
+}
@@ -0,0 +1,36 @@
+extern crate plcrash;
+
+use plcrash::arch::Arch;
+use plcrash::mach_exception::*;
+
+fn exception(exception_type: u64, codes: &[u64], arch: Arch) -> MachException {
+    MachException { exception_type, codes: codes.to_vec(), arch }
+}
+
+#[test]
+fn it_decodes_mach_exceptions() {
+    let bad_access = exception(EXC_BAD_ACCESS, &[KERN_INVALID_ADDRESS, 0x18], Arch::X86_64);
+    assert_eq!(bad_access.type_description("SIGSEGV"), "EXC_BAD_ACCESS (SIGSEGV)");
+    assert_eq!(bad_access.codes_description(), "KERN_INVALID_ADDRESS at 0x0000000000000018");
+    assert_eq!(bad_access.fault_address(), Some(0x18));
+
+    let align = exception(EXC_BAD_ACCESS, &[EXC_ARM_DA_ALIGN, 0x1001], Arch::Arm);
+    assert_eq!(align.codes_description(), "EXC_ARM_DA_ALIGN at 0x00001001");
+    assert_eq!(exception(EXC_BAD_ACCESS, &[EXC_ARM_DA_ALIGN, 0x1001], Arch::X86_64).code_name(), None);
+
+    let guard = exception(EXC_GUARD, &[(2 << 61) | (4 << 32) | 7, 0xdead], Arch::Arm64);
+    assert_eq!(guard.guard_type(), Some(GuardType::Fd));
+    assert_eq!(guard.codes_description(), "GUARD_TYPE_FD (flavor 0x4, target 0x7), id 0xdead");
+
+    let cpu = exception(EXC_RESOURCE, &[(1 << 61) | (1 << 58) | (180 << 7) | 50, 80], Arch::Arm64);
+    assert_eq!(cpu.codes_description(), "RESOURCE_TYPE_CPU (limit 50% over 180 seconds, observed 80%)");
+    /* The interval takes 25 bits */
+    let fatal_cpu = exception(EXC_RESOURCE, &[(1 << 61) | (2 << 58) | (0x30_0000 << 7) | 90, 95], Arch::Arm64);
+    assert_eq!(fatal_cpu.resource_limit(), Some(ResourceLimit::Cpu { fatal: true, limit: 90, interval: 0x30_0000, observed: 95 }));
+    let memory = exception(EXC_RESOURCE, &[(3 << 61) | (1 << 58) | 1450, 0], Arch::Arm64);
+    assert_eq!(memory.resource_limit(), Some(ResourceLimit::Memory { limit: 1450 }));
+
+    let crash = exception(EXC_CRASH, &[(6 << 24) | (1 << 20), 0], Arch::Arm64);
+    assert_eq!(crash.crash_origin(), Some((EXC_BAD_ACCESS, 6)));
+    assert_eq!(exception(0x42, &[1], Arch::Arm64).type_description(""), "0x42");
+}
@@ -0,0 +1,40 @@
+extern crate plcrash;
+
+use std::fs::File;
+
+use plcrash::fingerprint::{self, FingerprintOptions};
+use plcrash::reason::ReasonNormalizer;
+use plcrash::Options;
+
+#[test]
+fn it_normalizes_exception_reasons() {
+    let normalizer = ReasonNormalizer::new();
+    assert_eq!(normalizer.normalize("*** -[__NSArrayM objectAtIndex:]: index 5 beyond bounds [0 .. 3]"),
+        "*** -[__NSArrayM objectAtIndex:]: index <num> beyond bounds [<num> .. <num>]");
+    assert_eq!(normalizer.normalize("-[MZItem title]: unrecognized selector sent to instance 0x600000c2f1e0"),
+        "-[MZItem title]: unrecognized selector sent to instance <addr>");
+    assert_eq!(normalizer.normalize("Can't remove <MZItem: 0x6000002b4f20> for key 'title.value'"),
+        "Can't remove <MZItem: <addr>> for key '<str>'");
+    assert_eq!(normalizer.normalize("No document \"Movie.m4v\" for 5A537CE0-C887-3373-B4D9-2196436A4F14"),
+        "No document \"<str>\" for <uuid>");
+    assert_eq!(normalizer.normalize("NSArray0 has no UTF8 value"), "NSArray0 has no UTF8 value");
+}
+
+#[test]
+fn it_groups_similar_reasons() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let options = Options::default();
+    let fingerprint_options = FingerprintOptions::default();
+
+    report.mut_exception().set_name("NSRangeException".into());
+    report.mut_exception().set_reason("index 5 beyond bounds [0 .. 3]".into());
+    let first = fingerprint::fingerprint(&report, None, &options, &fingerprint_options).unwrap();
+    report.mut_exception().set_reason("index 12 beyond bounds [0 .. 9]".into());
+    let second = fingerprint::fingerprint(&report, None, &options, &fingerprint_options).unwrap();
+    assert_eq!(first.hash, second.hash);
+    assert_eq!(second.exception_reason, Some("index <num> beyond bounds [<num> .. <num>]".into()));
+
+    report.mut_exception().set_reason("index 12 beyond bounds for empty array".into());
+    assert_ne!(fingerprint::fingerprint(&report, None, &options, &fingerprint_options).unwrap().hash, first.hash);
+}
@@ -0,0 +1,62 @@
+extern crate plcrash;
+
+use std::fs::File;
+
+use plcrash::backtrace::symbolicate_thread;
+use plcrash::fault;
+use plcrash::protos::crash_report::{CrashReport, CrashReport_Thread_StackFrame};
+use plcrash::recursion::{self, RecursionAnalysis, StackCondition};
+use plcrash::Options;
+
+fn analyze(report: &CrashReport, options: &Options) -> Option<RecursionAnalysis> {
+    let frames = symbolicate_thread(&report.get_threads()[0], report, None, options).unwrap();
+    recursion::analyze(report, &frames, fault::classify(report, None).as_ref())
+}
+
+#[test]
+fn it_collapses_recursion() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let options = Options::default();
+    assert_eq!(analyze(&report, &options), None);
+
+    /* Repeat MetaZ frames 1-3 of the crashed thread, like mutual recursion would */
+    {
+        let frames = report.mut_threads()[0].mut_frames();
+        let cycle : Vec<CrashReport_Thread_StackFrame> = frames.iter().skip(1).take(3).cloned().collect();
+        let rest : Vec<CrashReport_Thread_StackFrame> = frames.iter().skip(4).cloned().collect();
+        frames.truncate(1);
+        for frame in cycle.iter().cycle().take(150).chain(rest.iter()) {
+            frames.push(frame.clone());
+        }
+        for (idx, name) in ["_a", "_b", "_c"].iter().enumerate() {
+            frames[idx + 1].mut_symbol().set_name(name.to_string());
+        }
+    }
+
+    let analysis = analyze(&report, &options).unwrap();
+    assert_eq!(analysis.condition, StackCondition::Recursion);
+    assert_eq!(analysis.cycles.len(), 1);
+    assert_eq!((analysis.cycles[0].start, analysis.cycles[0].end, analysis.cycles[0].repeats), (1, 150, 50));
+    assert_eq!(analysis.cycles[0].frames, vec!["a", "b", "c"]);
+
+    let text = plcrash::text_report(&report, None).unwrap();
+    assert!(text.contains("Stack:           recursion, thread 0 frames 1\u{2013}150 repeat [a \u{2192} b \u{2192} c] \u{d7}50\n"));
+    assert!(text.contains("\n... frames 1\u{2013}150 repeat [a \u{2192} b \u{2192} c] \u{d7}50\n151 "));
+    assert!(!text.contains("\n150 "));
+
+    /* A fault above the stack pointer is in a live frame, not an overflow */
+    let sp = report.get_threads()[0].get_registers().iter().find(|reg| reg.get_name() == "rsp").unwrap().get_value();
+    report.mut_signal().set_address(sp + 0x100);
+    let analysis = analyze(&report, &options).unwrap();
+    assert_eq!(analysis.condition, StackCondition::Recursion);
+
+    report.mut_signal().set_address(sp - 0x100);
+    let analysis = analyze(&report, &options).unwrap();
+    assert_eq!(analysis.condition, StackCondition::ProbableStackOverflow);
+
+    let options = Options { collapse_recursion: false, ..Options::default() };
+    let text = plcrash::text_report_with_options(&report, None, &options).unwrap();
+    assert!(text.contains("\n150 "));
+    assert!(plcrash::json_report(&report, None, &options).unwrap().contains("\"condition\": \"probable_stack_overflow\""));
+}
@@ -0,0 +1,54 @@
+extern crate plcrash;
+extern crate protobuf;
+
+use std::fs::File;
+
+use plcrash::arch::Arch;
+use plcrash::registers::{self, RegisterRole, ThreadState};
+use protobuf::RepeatedField;
+
+#[test]
+fn it_annotates_registers_pointing_into_images() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let report = plcrash::read_report(&mut report_file).unwrap();
+    let thread = report.get_threads().iter().find(|thread| thread.get_crashed()).unwrap();
+
+    let annotations = registers::annotate_registers(&report, thread, None).unwrap();
+    let names : Vec<&str> = annotations.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, vec!["rip", "rsi", "r11", "r12", "r14", "r15"]);
+    assert_eq!(annotations[0].to_string(), "libobjc.A.dylib + 0x6e9d");
+    assert_eq!(annotations[0].value, thread.get_frames()[0].get_pc());
+}
+
+#[test]
+fn it_types_thread_state() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let report = plcrash::read_report(&mut report_file).unwrap();
+    let thread = report.get_threads().iter().find(|thread| thread.get_crashed()).unwrap();
+
+    let state = ThreadState::new(Arch::of(&report), thread).unwrap();
+    assert_eq!(state.pc(), thread.get_frames()[0].get_pc());
+    assert_eq!(state.lr(), None);
+    let names : Vec<&str> = state.registers().iter().map(|reg| reg.0).collect();
+    assert_eq!(&names[..8], &["rax", "rbx", "rcx", "rdx", "rdi", "rsi", "rbp", "rsp"]);
+    assert_eq!(names[16..18], ["rip", "rfl"]);
+    assert_eq!(ThreadState::new(Arch::Arm64, thread), None);
+}
+
+#[test]
+fn it_finds_registers_by_role() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let rsp = report.get_threads()[0].get_registers().iter().find(|reg| reg.get_name() == "rsp").unwrap().get_value();
+
+    /* A thread missing other registers still has its stack pointer, also when the architecture is unknown */
+    let registers = report.get_threads()[0].get_registers().iter().filter(|reg| reg.get_name() != "rax").cloned().collect();
+    report.mut_threads()[0].set_registers(RepeatedField::from_vec(registers));
+    let thread = &report.get_threads()[0];
+    assert_eq!(ThreadState::new(Arch::X86_64, thread), None);
+    assert_eq!(registers::find_by_role(Arch::X86_64, thread, RegisterRole::StackPointer), Some(rsp));
+    assert_eq!(registers::find_by_role(Arch::Unknown, thread, RegisterRole::StackPointer), Some(rsp));
+    assert_eq!(registers::find_by_role(Arch::X86_64, thread, RegisterRole::ProgramCounter), Some(thread.get_frames()[0].get_pc()));
+    assert_eq!(registers::find_by_role(Arch::X86_64, thread, RegisterRole::LinkRegister), None);
+    assert_eq!(registers::find_by_role(Arch::X86_64, thread, RegisterRole::Argument(9)), None);
+}
@@ -0,0 +1,45 @@
+extern crate plcrash;
+
+use std::fs::File;
+
+use plcrash::regression::{self, RegressionOptions};
+use plcrash::{Corpus, Options};
+
+#[test]
+fn it_diffs_versions() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let options = Options::default();
+    let mut corpus = Corpus::default();
+
+    let batches = [
+        ("1.0", "SIGBUS", 20),
+        ("1.0", "SIGSEGV", 2),
+        ("1.0", "SIGABRT", 4),
+        ("1.1", "SIGBUS", 10),
+        ("1.1", "SIGSEGV", 12),
+        ("1.1", "SIGILL", 1),
+    ];
+    for &(version, signal, count) in &batches {
+        report.mut_application_info().set_version(version.into());
+        report.mut_signal().set_name(signal.into());
+        for _ in 0..count {
+            corpus.add(&report, None, &options).unwrap();
+        }
+    }
+
+    let diff = regression::compare_versions(&corpus, "1.0", "1.1", &RegressionOptions::default());
+    assert_eq!((diff.old_reports, diff.new_reports), (26, 23));
+    assert_eq!(diff.new.len(), 1);
+    assert!(diff.new[0].fingerprint.crash_type.starts_with("SIGILL"));
+    assert_eq!(diff.disappeared.len(), 1);
+    assert!(diff.disappeared[0].fingerprint.crash_type.starts_with("SIGABRT"));
+    assert_eq!(diff.increased.len(), 1);
+    assert!(diff.increased[0].fingerprint.crash_type.starts_with("SIGSEGV"));
+    assert_eq!((diff.increased[0].old_count, diff.increased[0].new_count), (2, 12));
+
+    /* The whole corpus against itself has no changes */
+    let same = regression::compare(&corpus, &corpus, &RegressionOptions::default());
+    assert!(same.new.is_empty() && same.increased.is_empty() && same.disappeared.is_empty());
+    assert!(diff.to_json().unwrap().contains("\"increased\""));
+}
@@ -0,0 +1,29 @@
+extern crate plcrash;
+
+use plcrash::signal::{Signal, SignalKind};
+
+#[test]
+fn it_normalizes_signal_codes() {
+    let by_name = Signal::parse("SIGSEGV", "SEGV_MAPERR", 0x18);
+    let by_number = Signal::parse("SIGSEGV", "#1", 0x18);
+    assert_eq!(by_name, by_number);
+    assert_eq!(by_name.kind, SignalKind::Sigsegv);
+    assert_eq!(by_name.code, Some(1));
+    assert_eq!(by_name.to_string(), "SIGSEGV (SEGV_MAPERR)");
+    assert_eq!(by_name.explanation(), "Invalid memory access: address not mapped to an object");
+
+    assert_eq!(Signal::parse("SIGBUS", "1", 0).code_name, Some("BUS_ADRALN"));
+    assert_eq!(Signal::parse("SIGFPE", "FPE_INTDIV", 0).code, Some(7));
+    assert_eq!(Signal::parse("SIGABRT", "0x10001", 0).code_name, Some("SI_USER"));
+
+    let unknown = Signal::parse("SIGSEGV", "#42", 0);
+    assert_eq!(unknown.code_name, None);
+    assert_eq!(unknown.code_display(), "#42");
+
+    /* A name we don't know is shown as the reporter wrote it */
+    let unnamed = Signal::parse("SIGSEGV", "SEGV_ACCADI", 0);
+    assert_eq!((unnamed.code_name, unnamed.code), (None, None));
+    assert_eq!(unnamed.code_display(), "SEGV_ACCADI");
+    assert_eq!(unnamed.to_string(), "SIGSEGV (SEGV_ACCADI)");
+    assert_eq!(Signal::parse("SIGPIPE", "#0", 0).kind, SignalKind::Other("SIGPIPE".into()));
+}
@@ -0,0 +1,49 @@
+extern crate addr2line;
+extern crate plcrash;
+
+use std::fs;
+
+use plcrash::source::SourceStatus;
+use plcrash::{Location, PathMap, SourceRoot};
+
+fn location(file: &str, line: u64) -> Location {
+    Location(addr2line::Location {
+        file: Some(file.into()),
+        line: Some(line),
+        column: Some(5),
+    })
+}
+
+#[test]
+fn it_reads_source_context() {
+    let root = std::env::temp_dir().join("plcrash-source-test");
+    fs::create_dir_all(root.join("App/src")).unwrap();
+    let lines : Vec<String> = (1..=10).map(|n| format!("line {}", n)).collect();
+    fs::write(root.join("App/src/MZWriteQueue.m"), lines.join("\n")).unwrap();
+
+    let source = SourceRoot::new(&root);
+    let dwarf_path = "/Users/bro/Documents/Maven-Group/MetaZ/App/src/MZWriteQueue.m";
+    assert_eq!(source.relative_path(dwarf_path), Some("App/src/MZWriteQueue.m".into()));
+
+    let context = source.context(&location(dwarf_path, 2), 2).unwrap();
+    assert_eq!(context.status, SourceStatus::Found);
+    let numbers : Vec<u64> = context.lines.iter().map(|l| l.number).collect();
+    assert_eq!(numbers, vec![1, 2, 3, 4]);
+    assert_eq!(context.lines[1].text, "line 2");
+    assert_eq!(context.column, Some(5));
+
+    assert_eq!(format!("{}", location("MZWriteQueue.m", 408)), "MZWriteQueue.m:408");
+    assert_eq!(format!("{:#}", location("MZWriteQueue.m", 408)), "MZWriteQueue.m:408:5");
+
+    let context = source.context(&location(dwarf_path, 408), 2).unwrap();
+    assert_eq!(context.status, SourceStatus::Short);
+
+    let context = source.context(&location("/Users/bro/Missing.m", 1), 2).unwrap();
+    assert_eq!(context.status, SourceStatus::Missing);
+
+    let mut paths = PathMap::new();
+    paths.add("/Users/bro/Documents/Maven-Group/MetaZ", "");
+    let source = SourceRoot::with_path_map(&root, paths);
+    assert_eq!(source.resolve(dwarf_path), Some(root.join("App/src/MZWriteQueue.m")));
+    assert_eq!(source.resolve("/Users/bro/Documents/Maven-Group/MetaZ/App/MZWriteQueue.m"), None);
+}
@@ -0,0 +1,58 @@
+extern crate plcrash;
+
+use plcrash::symbol::{Language, MethodKind, SymbolName};
+
+#[test]
+fn it_parses_objc_methods() {
+    let name = SymbolName::parse("-[NSWindow(NSEventRouting) sendEvent:]");
+    assert_eq!(name.language, Language::ObjectiveC);
+    assert_eq!(name.type_name, Some("NSWindow".into()));
+    assert_eq!(name.category, Some("NSEventRouting".into()));
+    assert_eq!(name.method, Some("sendEvent:".into()));
+    assert_eq!(name.method_kind, Some(MethodKind::Instance));
+
+    let name = SymbolName::parse("+[MZPluginController sharedInstance]");
+    assert_eq!(name.type_name, Some("MZPluginController".into()));
+    assert_eq!(name.category, None);
+    assert_eq!(name.method_kind, Some(MethodKind::Class));
+}
+
+#[test]
+fn it_parses_swift_names() {
+    let name = SymbolName::parse("MetaZ.AppDelegate.applicationDidFinishLaunching(Foundation.Notification) -> ()");
+    assert_eq!(name.language, Language::Swift);
+    assert_eq!(name.module, Some("MetaZ".into()));
+    assert_eq!(name.type_name, Some("AppDelegate".into()));
+    assert_eq!(name.method, Some("applicationDidFinishLaunching".into()));
+    assert_eq!(name.qualified_type(), Some("MetaZ.AppDelegate".into()));
+
+    let name = SymbolName::parse("closure #1 in static MetaZ.Search.Provider.load(from: Swift.String) -> ()");
+    assert_eq!(name.type_name, Some("Search.Provider".into()));
+    assert_eq!(name.method, Some("load".into()));
+    assert_eq!(name.method_kind, Some(MethodKind::Class));
+
+    let name = SymbolName::parse("MetaZ.Document.title.getter : Swift.String");
+    assert_eq!(name.type_name, Some("Document".into()));
+    assert_eq!(name.method, Some("title.getter".into()));
+
+    assert_eq!(SymbolName::parse("$s5MetaZ11AppDelegateC").language, Language::Swift);
+}
+
+#[test]
+fn it_parses_cpp_names() {
+    let name = SymbolName::parse("std::__1::vector<int, std::__1::allocator<int> >::push_back(int const&)");
+    assert_eq!(name.language, Language::Cpp);
+    assert_eq!(name.namespaces, vec![String::from("std"), String::from("__1")]);
+    assert_eq!(name.type_name, Some("vector<int, std::__1::allocator<int> >".into()));
+    assert_eq!(name.method, Some("push_back".into()));
+
+    assert_eq!(SymbolName::parse("__ZN5MetaZ4loadEv").language, Language::Cpp);
+}
+
+#[test]
+fn it_falls_back_to_c() {
+    let name = SymbolName::parse("objc_msgSend");
+    assert_eq!(name.language, Language::C);
+    assert_eq!(name.method, Some("objc_msgSend".into()));
+    assert_eq!(SymbolName::parse("compress.cold.1").language, Language::C);
+}
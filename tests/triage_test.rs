@@ -0,0 +1,51 @@
+extern crate plcrash;
+
+use std::cmp::Ordering;
+use std::fs::File;
+
+use plcrash::backtrace::symbolicate_thread;
+use plcrash::triage::{self, Tag};
+use plcrash::{Options, TriageRules};
+
+const RULES : &str = r#"[
+    {"tag": "nil-access", "note": "Field access through nil", "signal": "SEGV_MAPERR", "images": ["libobjc*"]},
+    {"tag": "high-sierra", "os_min": "10.13", "os_max": "10.14", "models": ["MacBookPro*"]},
+    {"tag": "rosetta", "native": false},
+    {"tag": "range", "exception_name": "^NSRangeException$"},
+    {"tag": "objc-msgsend", "symbols": ["objc_msgSend*"]}
+]"#;
+
+#[test]
+fn it_tags_matching_reports() {
+    let mut report_file = File::open("tests/MetaZ.plcrash").unwrap();
+    let mut report = plcrash::read_report(&mut report_file).unwrap();
+    let rules = TriageRules::from_json(RULES).unwrap();
+    let options = Options { triage: Some(&rules), ..Options::default() };
+
+    let tags = rules.evaluate(&report, &symbolicate_thread(&report.get_threads()[0], &report, None, &options).unwrap());
+    let names : Vec<&str> = tags.iter().map(|tag| tag.tag.as_str()).collect();
+    assert_eq!(names, vec!["nil-access", "high-sierra"]);
+    assert_eq!(tags[0], Tag { tag: "nil-access".into(), note: Some("Field access through nil".into()) });
+
+    report.mut_process_info().set_native(false);
+    report.mut_system_info().set_os_version("10.14".into());
+    let tags = rules.evaluate(&report, &symbolicate_thread(&report.get_threads()[0], &report, None, &options).unwrap());
+    assert_eq!(tags.iter().map(|tag| tag.tag.as_str()).collect::<Vec<_>>(), vec!["nil-access", "rosetta"]);
+
+    let text = plcrash::text_report_with_options(&report, None, &options).unwrap();
+    assert!(text.contains("Triage Tags:\n   nil-access: Field access through nil\n   rosetta\n"));
+    assert!(plcrash::json_report(&report, None, &options).unwrap().contains("\"tag\": \"rosetta\""));
+
+    /* Unknown is neither native nor translated */
+    report.mut_process_info().clear_native();
+    let tags = rules.evaluate(&report, &symbolicate_thread(&report.get_threads()[0], &report, None, &options).unwrap());
+    assert_eq!(tags.iter().map(|tag| tag.tag.as_str()).collect::<Vec<_>>(), vec!["nil-access"]);
+
+    report.mut_threads()[0].mut_frames()[0].mut_symbol().set_name("_objc_msgSend".into());
+    let tags = rules.evaluate(&report, &symbolicate_thread(&report.get_threads()[0], &report, None, &options).unwrap());
+    assert_eq!(tags.iter().map(|tag| tag.tag.as_str()).collect::<Vec<_>>(), vec!["nil-access", "objc-msgsend"]);
+
+    assert!(TriageRules::from_json(r#"[{"tag": "typo", "signl": "SIGSEGV"}]"#).is_err());
+    assert_eq!(triage::compare_versions("10.13.6", "10.13"), Ordering::Greater);
+    assert_eq!(triage::compare_versions("10.9", "10.13"), Ordering::Less);
+}